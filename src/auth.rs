@@ -0,0 +1,253 @@
+// src/auth.rs
+
+//! Optional login and origin checks
+//!
+//! Authentication is off unless `AUTH_SECRET` (one shared secret) or `AUTH_TOKENS`
+//! (comma separated `user:token` pairs) is set. Clients log in with the secret or their
+//! token at `POST /api/auth/login` and get a session cookie; scripts can instead send the
+//! token as `Authorization: Bearer <token>`. The `require_login` middleware then rejects
+//! unauthenticated `/api` and `/ws` requests with 401 and redirects page requests to the
+//! login page. Sessions live in memory, so a restart logs everybody out.
+//!
+//! Independently of the login, `ALLOWED_ORIGINS` lists the web origins other than the
+//! player itself that may call the API (`*` for any). It configures CORS and is also
+//! enforced on the `/ws` handshake and on every non-GET request, since browsers apply
+//! neither CORS to WebSockets nor preflights to plain form posts.
+
+use actix_cors::Cors;
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{Method, header},
+    middleware::Next,
+};
+use log::warn;
+use once_cell::sync::{Lazy, OnceCell};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub const SESSION_COOKIE: &str = "hfp_session";
+pub const LOGIN_PAGE: &str = "/site/login";
+const DEFAULT_SESSION_HOURS: u64 = 168;
+/// Name given to sessions logged in with the shared secret.
+const SHARED_USER: &str = "shared";
+
+/// Paths reachable without logging in.
+const PUBLIC_PATHS: &[&str] = &["/api/auth/", LOGIN_PAGE, "/site/static/"];
+
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    pub shared_secret: Option<String>,
+    /// Token -> user name.
+    pub tokens: HashMap<String, String>,
+    pub session_lifetime: Duration,
+    /// Extra origins allowed to call the API; `None` allows any.
+    pub allowed_origins: Option<Vec<String>>,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Self {
+        let shared_secret = env::var("AUTH_SECRET").ok().filter(|s| !s.trim().is_empty());
+
+        let mut tokens = HashMap::new();
+        for entry in env::var("AUTH_TOKENS").unwrap_or_default().split(',') {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            match entry.split_once(':') {
+                Some((user, token)) if !user.trim().is_empty() && !token.trim().is_empty() => {
+                    tokens.insert(token.trim().to_string(), user.trim().to_string());
+                }
+                _ => warn!("Ignoring AUTH_TOKENS entry without user:token form"),
+            }
+        }
+
+        let session_hours = env::var("AUTH_SESSION_HOURS")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(DEFAULT_SESSION_HOURS);
+
+        let allowed_origins = match env::var("ALLOWED_ORIGINS") {
+            Ok(raw) if raw.trim() == "*" => None,
+            Ok(raw) => Some(
+                raw.split(',')
+                    .map(|o| o.trim().trim_end_matches('/').to_string())
+                    .filter(|o| !o.is_empty())
+                    .collect(),
+            ),
+            Err(_) => Some(Vec::new()),
+        };
+
+        Self {
+            shared_secret,
+            tokens,
+            session_lifetime: Duration::from_secs(session_hours * 3600),
+            allowed_origins,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.shared_secret.is_some() || !self.tokens.is_empty()
+    }
+
+    /// User name for a shared secret or token, compared by digest so the comparison
+    /// takes the same time wherever the input differs.
+    fn user_for(&self, secret: &str) -> Option<String> {
+        let digest = Sha256::digest(secret.as_bytes());
+        let matches = |candidate: &str| Sha256::digest(candidate.as_bytes()) == digest;
+
+        if self.shared_secret.as_deref().is_some_and(matches) {
+            return Some(SHARED_USER.to_string());
+        }
+        self.tokens
+            .iter()
+            .find(|(token, _)| matches(token))
+            .map(|(_, user)| user.clone())
+    }
+
+    /// CORS policy for the configured origins. The player's own pages are same-origin
+    /// and need no entry.
+    pub fn cors(&self) -> Cors {
+        let cors = Cors::default()
+            .allowed_methods(vec!["GET", "POST", "OPTIONS"])
+            .allow_any_header()
+            .max_age(3600);
+        match &self.allowed_origins {
+            None => cors.allow_any_origin(),
+            Some(origins) => origins
+                .iter()
+                .fold(cors.supports_credentials(), |cors, origin| cors.allowed_origin(origin)),
+        }
+    }
+}
+
+struct LoginSession {
+    user: String,
+    expires: Instant,
+}
+
+static CONFIG: OnceCell<AuthConfig> = OnceCell::new();
+static SESSIONS: Lazy<Mutex<HashMap<String, LoginSession>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The configuration, read from the environment on first use.
+pub fn config() -> &'static AuthConfig {
+    CONFIG.get_or_init(AuthConfig::from_env)
+}
+
+/// Check a shared secret or token and open a session for it. Returns the session id and
+/// the user name.
+pub fn login(secret: &str) -> Option<(String, String)> {
+    let config = config();
+    let user = config.user_for(secret)?;
+    let id = hex::encode(rand::random::<[u8; 32]>());
+
+    let mut sessions = SESSIONS.lock().unwrap();
+    let now = Instant::now();
+    sessions.retain(|_, s| s.expires > now);
+    sessions.insert(
+        id.clone(),
+        LoginSession {
+            user: user.clone(),
+            expires: now + config.session_lifetime,
+        },
+    );
+    Some((id, user))
+}
+
+pub fn logout(session_id: &str) {
+    SESSIONS.lock().unwrap().remove(session_id);
+}
+
+fn session_user(session_id: &str) -> Option<String> {
+    let mut sessions = SESSIONS.lock().unwrap();
+    match sessions.get(session_id) {
+        Some(s) if s.expires > Instant::now() => Some(s.user.clone()),
+        Some(_) => {
+            sessions.remove(session_id);
+            None
+        }
+        None => None,
+    }
+}
+
+/// The user a request is logged in as, from its session cookie or bearer token.
+pub fn request_user(req: &actix_web::HttpRequest) -> Option<String> {
+    if let Some(cookie) = req.cookie(SESSION_COOKIE)
+        && let Some(user) = session_user(cookie.value())
+    {
+        return Some(user);
+    }
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+    config().user_for(token.trim())
+}
+
+/// Whether the request's `Origin` (if any) is the player itself or an allowed origin.
+fn origin_allowed(req: &ServiceRequest) -> bool {
+    let Some(origin) = req.headers().get(header::ORIGIN).and_then(|o| o.to_str().ok()) else {
+        return true;
+    };
+    let origin = origin.trim_end_matches('/');
+    let host = req.connection_info().host().to_string();
+    let same_origin = origin
+        .split_once("://")
+        .is_some_and(|(_, origin_host)| origin_host.eq_ignore_ascii_case(&host));
+
+    same_origin
+        || match &config().allowed_origins {
+            None => true,
+            Some(origins) => origins.iter().any(|o| o.eq_ignore_ascii_case(origin)),
+        }
+}
+
+fn is_public(path: &str) -> bool {
+    PUBLIC_PATHS.iter().any(|p| path.starts_with(p))
+}
+
+fn is_api(path: &str) -> bool {
+    path.starts_with("/api") || path == "/ws"
+}
+
+/// Middleware enforcing the origin list and, when enabled, the login.
+pub async fn require_login(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let path = req.path().to_string();
+
+    let checks_origin = req.method() != Method::GET || path == "/ws";
+    if checks_origin && req.method() != Method::OPTIONS && !origin_allowed(&req) {
+        warn!("Rejected {} {} from a foreign origin", req.method(), path);
+        return Ok(req.into_response(HttpResponse::Forbidden().body("Origin not allowed")));
+    }
+
+    if !config().enabled()
+        || req.method() == Method::OPTIONS
+        || is_public(&path)
+        || request_user(req.request()).is_some()
+    {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let response = if is_api(&path) {
+        HttpResponse::Unauthorized().body("Login required")
+    } else {
+        let target = format!("{LOGIN_PAGE}?next={}", req.uri().path());
+        HttpResponse::SeeOther()
+            .insert_header((header::LOCATION, target))
+            .finish()
+    };
+    Ok(req.into_response(response))
+}
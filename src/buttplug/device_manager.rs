@@ -3,188 +3,1207 @@
 //! Device connection and control module
 //!
 //! This module manages communication with hardware devices through the Buttplug protocol.
-//! It coordinates scanning, connects compatible devices (oscillate/vibrate), and runs a
-//! small control loop that periodically sends the latest intensity values to connected devices.
+//! A supervisor task keeps the connection alive, reconnecting with exponential backoff
+//! after the server goes away. It coordinates scanning, keeps a registry of connected
+//! devices keyed by Buttplug device index, and runs a small control loop that periodically
+//! sends the latest intensity values to every actuator according to its assigned role,
+//! shaped by the actuator's output curve and capped by the global max limit. A value is
+//! only sent when it moves to another step of the actuator's resolution, or when the
+//! keep-alive interval has passed, to keep Bluetooth traffic down; per-device counters
+//! track sent, skipped and failed commands. Devices are reached through `OutputDevice`,
+//! so a simulated device can replace Intiface (see `OutputBackend`). Linear actuators are
+//! sent the next action of the raw script instead, once per action, and rotate actuators
+//! flip direction with the strokes of the raw script when assigned the alternating role.
+//! Endpoint and loop timings come from `IntifaceConfig` and can be changed at runtime.
 //!
-//! The module exposes a global singleton managed by a OnceCell and convenience async wrappers
-//! for setting current intensities from other parts of the application.
+//! The intensity timeline itself is owned by a server-side `PlaybackEngine`: clients only
+//! report play/pause/seek/sync events and the control loop interpolates the values to send.
+//! A watchdog halts the engine when no client command arrives within the configured timeout,
+//! so a crashed browser or locked phone does not leave the device running. An emergency
+//! stop zeroes every device through Intiface and latches until a client re-arms.
+//!
+//! Battery level and signal strength are polled periodically. Connection changes, device
+//! arrivals and removals, telemetry and low-battery warnings are published as
+//! `DeviceEvent`s, alongside stop state and max limit changes, for the WebSocket layer to
+//! push to clients; a low battery can optionally stop playback. The values the devices
+//! last received are published after every control tick as an `AppliedOutput`.
+//!
+//! That output can be recorded into a session file and replayed later through the same
+//! control loop in place of the playback engine (see `session_recording`). Each tick is
+//! also fanned out as a `SinkFrame` to the configured `OutputSink`s (OSC, MQTT).
+//!
+//! The module exposes a global singleton managed by a OnceCell and convenience wrappers
+//! for driving playback from other parts of the application.
 
+use crate::buttplug::{
+    device_settings::{
+        self, ActuatorCurve, ActuatorCurveUpdate, ActuatorKind, ActuatorRole, DeviceSettings,
+        DeviceSettingsStore, DeviceSettingsUpdate,
+    },
+    funscript_utils::Action,
+    intiface_config::{IntifaceConfig, IntifaceConfigUpdate},
+    output::{OutputBackend, OutputDevice},
+    output_sink::{self, OutputSink, SinkFrame},
+    playback::{LinearTarget, PlaybackEngine, PlaybackOutput},
+    session_recording::{
+        RecordedDevice, RecordedValue, Recorder, Replay, ReplayValues, SessionMeta, SessionRecording,
+    },
+    virtual_device::{RecordedCommand, VIRTUAL_DEVICE_INDEX, VirtualDevice, VirtualState},
+};
 use atomic_float::AtomicF64;
 use buttplug::{
     client::{
         ButtplugClient, ButtplugClientError, ButtplugClientEvent,
-        device::{ButtplugClientDevice, ScalarValueCommand},
     },
     core::{connector::new_json_ws_client_connector, message::ActuatorType},
 };
 use futures::StreamExt;
 use log::{error, info, warn};
 use once_cell::sync::OnceCell;
-use std::{sync::{Arc, RwLock}, time::Duration};
-use tokio::sync::Mutex;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex, Notify, broadcast, watch};
 
 static MANAGER: OnceCell<Arc<DeviceManager>> = OnceCell::new();
 
-const SERVER_URL: &str = "ws://127.0.0.1:12345/buttplug";
-const CONTROL_INTERVAL_MS: u64 = 100;
-const SCAN_INTERVAL_SECS: u64 = 5;
-const RECONNECT_DELAY_SECS: u64 = 5;
+/// Events buffered per subscriber before the slowest one starts missing them.
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// (device index, command family, actuator index)
+type ActuatorKey = (u32, ActuatorKind, u32);
+
+/// (playback epoch, action index) of a linear move.
+type LinearMoveKey = (u64, usize);
+
+/// Largest position jump between two ticks that still reports the beats in between.
+const MAX_BEAT_GAP_MS: f64 = 1000.0;
+
+/// How long to keep scanning for a missing stored device while other devices are
+/// connected, after the set of connected devices last changed.
+const MISSING_DEVICE_SCAN_LIMIT: Duration = Duration::from_secs(60);
+
+/// Values shared by every device during one control tick.
+struct Tick {
+    now: Instant,
+    output: PlaybackOutput,
+    ramp_start: Instant,
+    max_limit: f64,
+    /// Resend unchanged values after this long; `None` sends only on change.
+    keepalive: Option<Duration>,
+    /// Recorded values to send instead of the engine output while a replay runs.
+    replay: Option<ReplayValues>,
+}
+
+/// Output state of one scalar/rotate actuator.
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    /// Last rate-limited value, the base for slew limiting.
+    base: f64,
+    /// `base` capped by the soft-start ramp, the value to send.
+    value: f64,
+    at: Instant,
+    /// What the device last received.
+    sent: Option<SentLevel>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SentLevel {
+    value: f64,
+    step: u32,
+    clockwise: bool,
+    at: Instant,
+}
+
+impl Level {
+    /// Whether `step`/`clockwise` has to be sent, because it differs from what the
+    /// device last received or the keep-alive interval has passed.
+    fn needs_send(&self, step: u32, clockwise: bool, tick: &Tick) -> bool {
+        match self.sent {
+            Some(sent) => {
+                sent.step != step
+                    || sent.clockwise != clockwise
+                    || tick
+                        .keepalive
+                        .is_some_and(|keepalive| tick.now.saturating_duration_since(sent.at) >= keepalive)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Commands sent to one device since startup.
+#[derive(Serialize, Debug, Clone, Copy, Default)]
+pub struct CommandCounters {
+    pub sent: u64,
+    /// Ticks where nothing changed and the keep-alive was not due.
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+impl CommandCounters {
+    pub fn add(&mut self, other: &CommandCounters) {
+        self.sent += other.sent;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
+    }
+}
+
+/// State of the supervised connection to the Intiface server.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Scanning,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ActuatorInfo {
+    pub kind: ActuatorKind,
+    pub index: u32,
+    pub actuator_type: ActuatorType,
+    pub step_count: u32,
+    pub description: String,
+    pub role: ActuatorRole,
+    /// Value (speed, or position for linear actuators) last delivered to the device.
+    pub last_value: Option<f64>,
+    /// Output curve; absent for linear actuators.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve: Option<ActuatorCurve>,
+}
 
-struct DevicePair {
-    oscillator: Option<Arc<ButtplugClientDevice>>,
-    vibrator: Option<Arc<ButtplugClientDevice>>,
+#[derive(Serialize, Debug, Clone)]
+pub struct DeviceInfo {
+    pub index: u32,
+    pub name: String,
+    #[serde(rename = "virtual")]
+    pub is_virtual: bool,
+    pub connected: bool,
+    /// Battery level (0.0 .. 1.0) when the device reports one.
+    pub battery: Option<f64>,
+    /// Signal strength in dBm when the device reports it.
+    pub rssi: Option<i32>,
+    pub scale: f64,
+    pub soft_start_ms: u64,
+    pub actuators: Vec<ActuatorInfo>,
+    pub commands: CommandCounters,
+}
+
+/// Last battery and signal readings of a device.
+#[derive(Debug, Clone, Copy, Default)]
+struct Telemetry {
+    battery: Option<f64>,
+    rssi: Option<i32>,
+    /// Set once a low-battery warning went out, until the level recovers.
+    low_battery: bool,
+}
+
+/// Pushed to WebSocket clients as `{"device_event": ...}`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeviceEvent {
+    Connection {
+        state: ConnectionState,
+    },
+    DeviceAdded {
+        device: DeviceInfo,
+    },
+    DeviceRemoved {
+        index: u32,
+    },
+    Telemetry {
+        index: u32,
+        battery: Option<f64>,
+        rssi: Option<i32>,
+    },
+    LowBattery {
+        index: u32,
+        name: String,
+        battery: f64,
+        /// Whether playback was stopped because of it.
+        paused: bool,
+    },
+}
+
+/// Everything the device manager publishes to subscribers.
+#[derive(Debug, Clone)]
+pub enum ManagerEvent {
+    Device(DeviceEvent),
+    StopState { stopped: bool },
+    MaxLimit { limit: f64 },
+}
+
+/// Values the devices last received, published after every control tick.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AppliedOutput {
+    pub position_ms: f64,
+    pub playing: bool,
+    pub stopped: bool,
+    pub actuators: Vec<AppliedActuator>,
+}
+
+/// Progress of a running replay.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReplayStatus {
+    pub name: String,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// Transport state of the playback engine.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlaybackStatus {
+    /// Funscript path of the loaded timeline.
+    pub source: Option<String>,
+    pub playing: bool,
+    pub position_ms: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AppliedActuator {
+    pub device: u32,
+    pub kind: ActuatorKind,
+    pub index: u32,
+    pub actuator_type: ActuatorType,
+    pub role: ActuatorRole,
+    /// Speed, or position for linear actuators (0.0 .. 1.0).
+    pub value: f64,
 }
 
 struct DeviceManager {
-    devices: Arc<Mutex<DevicePair>>,
-    oscillate_intensity: AtomicF64,
-    vibrate_intensity: AtomicF64,
+    config: RwLock<IntifaceConfig>,
+    /// Wakes the connection supervisor so it reconnects to a changed server URL.
+    reconnect: Notify,
+    client: RwLock<Option<Arc<ButtplugClient>>>,
+    connection_state: RwLock<ConnectionState>,
+    devices: Arc<Mutex<BTreeMap<u32, OutputDevice>>>,
+    /// Simulated device while the virtual backend is active.
+    virtual_device: RwLock<Option<Arc<VirtualDevice>>>,
+    /// Last linear move sent per device, with its position.
+    linear_sent: Mutex<HashMap<u32, (LinearMoveKey, f64)>>,
+    /// Output state of each scalar/rotate actuator.
+    levels: Mutex<HashMap<ActuatorKey, Level>>,
+    /// Command counters keyed by device index, kept across reconnects.
+    command_counters: RwLock<BTreeMap<u32, CommandCounters>>,
+    telemetry: RwLock<HashMap<u32, Telemetry>>,
+    events: broadcast::Sender<ManagerEvent>,
+    applied: watch::Sender<AppliedOutput>,
+    settings: RwLock<DeviceSettingsStore>,
+    playback: RwLock<PlaybackEngine>,
     max_limit: AtomicF64,
+    /// Latched emergency stop; nothing is sent to devices while set.
+    stopped: AtomicBool,
+    /// Time of the last valid command received from a client.
+    last_command: RwLock<Instant>,
+    /// Script position at which the watchdog stopped playback, until playback resumes.
+    watchdog_stop: RwLock<Option<f64>>,
     active_calibration_points: RwLock<Vec<(f64, f64)>>, // [(bpm, intensity)]
+    active_calibration_name: RwLock<Option<String>>,
+    recorder: RwLock<Option<Recorder>>,
+    replay: RwLock<Option<Replay>>,
+    sinks: Vec<Arc<dyn OutputSink>>,
+    /// Script position of the previous control tick, the start for finding passed beats.
+    last_tick_position: RwLock<Option<f64>>,
+    /// Start of the scan for missing stored devices while others are connected; reset
+    /// whenever a device arrives or leaves.
+    missing_scan_since: RwLock<Option<Instant>>,
 }
 
 impl DeviceManager {
-    fn new() -> Arc<Self> {
+    fn new(config: IntifaceConfig, settings: DeviceSettingsStore, sinks: Vec<Arc<dyn OutputSink>>) -> Arc<Self> {
         Arc::new(Self {
-            devices: Arc::new(Mutex::new(DevicePair {
-                oscillator: None,
-                vibrator: None,
-            })),
-            oscillate_intensity: AtomicF64::new(0.0),
-            vibrate_intensity: AtomicF64::new(0.0),
-            max_limit: AtomicF64::new(1.0),
+            config: RwLock::new(config),
+            reconnect: Notify::new(),
+            client: RwLock::new(None),
+            connection_state: RwLock::new(ConnectionState::Disconnected),
+            devices: Arc::new(Mutex::new(BTreeMap::new())),
+            virtual_device: RwLock::new(None),
+            linear_sent: Mutex::new(HashMap::new()),
+            levels: Mutex::new(HashMap::new()),
+            command_counters: RwLock::new(BTreeMap::new()),
+            telemetry: RwLock::new(HashMap::new()),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            applied: watch::channel(AppliedOutput::default()).0,
+            settings: RwLock::new(settings),
+            playback: RwLock::new(PlaybackEngine::default()),
+            max_limit: AtomicF64::new(0.6),
+            stopped: AtomicBool::new(false),
+            last_command: RwLock::new(Instant::now()),
+            watchdog_stop: RwLock::new(None),
             active_calibration_points: RwLock::new(vec![(0.0, 0.0)]),
+            active_calibration_name: RwLock::new(None),
+            recorder: RwLock::new(None),
+            replay: RwLock::new(None),
+            sinks,
+            last_tick_position: RwLock::new(None),
+            missing_scan_since: RwLock::new(None),
         })
     }
 
-    async fn send_commands(&self) {
+    /// Stored settings for `device`, or defaults when the index is unknown or was
+    /// last used by a different device.
+    fn settings_for(&self, device: &OutputDevice) -> DeviceSettings {
+        let store = self.settings.read().unwrap_or_else(|e| e.into_inner());
+        store
+            .get(&device.index())
+            .filter(|s| s.name == device.name())
+            .cloned()
+            .unwrap_or_else(|| DeviceSettings::new(device.name()))
+    }
+
+    fn count(&self, device_index: u32, update: impl FnOnce(&mut CommandCounters)) {
+        let mut counters = self.command_counters.write().unwrap_or_else(|e| e.into_inner());
+        update(counters.entry(device_index).or_default());
+    }
+
+    async fn send_commands(&self, now: Instant) {
         let devices = self.devices.lock().await;
+        let mut levels = self.levels.lock().await;
 
-        let raw_osc = self
-            .oscillate_intensity
-            .load(std::sync::atomic::Ordering::Relaxed)
-            .clamp(0.0, 1.0);
-        let raw_vib = self
-            .vibrate_intensity
-            .load(std::sync::atomic::Ordering::Relaxed)
-            .clamp(0.0, 1.0);
+        let keepalive_ms = self.config().keepalive_ms;
+        let replay = self.replay_values(now);
+        let (tick, position_ms, playing) = {
+            let engine = self.playback.read().unwrap_or_else(|e| e.into_inner());
+            let tick = Tick {
+                now,
+                output: engine.output(now),
+                ramp_start: engine.ramp_start(),
+                max_limit: self.max_limit.load(Ordering::Relaxed),
+                keepalive: (keepalive_ms > 0).then(|| Duration::from_millis(keepalive_ms)),
+                replay,
+            };
+            (tick, engine.position_ms(now), engine.is_playing())
+        };
 
-        let max_limit = self.max_limit.load(std::sync::atomic::Ordering::Relaxed);        
-        let final_osc = raw_osc.min(max_limit).clamp(0.0, 1.0);
-        let final_vib = raw_vib; // .min(max_limit).clamp(0.0, 1.0);
+        let stopped = self.stopped.load(Ordering::SeqCst);
+        if !stopped {
+            for dev in devices.values() {
+                let settings = self.settings_for(dev);
+                self.send_scalar(dev, &settings, &tick, &mut levels).await;
+                self.send_rotate(dev, &settings, &tick, &mut levels).await;
+                if let Some(target) = tick.output.linear
+                    && tick.replay.is_none()
+                {
+                    self.send_linear(dev, &settings, target, tick.now).await;
+                }
+            }
+        }
 
-        if let Some(ref dev) = devices.oscillator {
-            if let Err(e) = dev.oscillate(&ScalarValueCommand::ScalarValue(final_osc)).await {
-                error!("Failed to send oscillate command: {}", e);
+        let linear_sent = self.linear_sent.lock().await;
+        let actuators = devices
+            .values()
+            .flat_map(|dev| {
+                let settings = self.settings_for(dev);
+                let last_value = |kind, i| last_sent_value(&levels, &linear_sent, dev.index(), kind, i);
+                actuator_infos(dev, &settings, last_value)
+                    .into_iter()
+                    .filter_map(|a| {
+                        Some(AppliedActuator {
+                            device: dev.index(),
+                            kind: a.kind,
+                            index: a.index,
+                            actuator_type: a.actuator_type,
+                            role: a.role,
+                            value: a.last_value?,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let beats = self.beats_since_last_tick(position_ms, playing);
+        if tick.replay.is_none() {
+            self.record_sample(now, position_ms, playing, beats.clone(), &actuators, &levels);
+        }
+        if !self.sinks.is_empty() {
+            let intensity = actuators
+                .iter()
+                .filter(|a| a.kind != ActuatorKind::Linear)
+                .map(|a| a.value)
+                .fold(0.0, f64::max);
+            let frame = SinkFrame {
+                intensity: if stopped { 0.0 } else { intensity },
+                position_ms,
+                playing,
+                stopped,
+                beats,
+            };
+            for sink in &self.sinks {
+                sink.publish(&frame);
             }
         }
+        self.applied.send_replace(AppliedOutput {
+            position_ms,
+            playing,
+            stopped,
+            actuators,
+        });
+    }
+
+    async fn send_scalar(
+        &self,
+        dev: &OutputDevice,
+        settings: &DeviceSettings,
+        tick: &Tick,
+        levels: &mut HashMap<ActuatorKey, Level>,
+    ) {
+        let specs = dev.actuators(ActuatorKind::Scalar);
+        if specs.is_empty() {
+            return;
+        }
 
-        if let Some(ref dev) = devices.vibrator {
-            if let Err(e) = dev.vibrate(&ScalarValueCommand::ScalarValue(final_vib)).await {
-                error!("Failed to send vibrate command: {}", e);
+        let mut scalars = HashMap::new();
+        let mut steps = Vec::new();
+        for (i, spec) in specs.iter().enumerate() {
+            let actuator = spec.actuator_type;
+            let role = settings.role_for(ActuatorKind::Scalar, i as u32, actuator);
+            let curve = settings.curve_for(ActuatorKind::Scalar, i as u32).unwrap_or_default();
+            let key = (dev.index(), ActuatorKind::Scalar, i as u32);
+            if is_idle_disabled(role, levels, key) {
+                continue;
+            }
+            let target = match &tick.replay {
+                Some(values) => values.get(&key).map_or(0.0, |(value, _)| *value).min(tick.max_limit),
+                None => curve.apply(role_value(role, &tick.output)).min(tick.max_limit) * settings.scale,
+            };
+
+            let limits = curve.slew_limits(role);
+            let level = update_level(levels, key, target, limits, settings, tick);
+            let step = quantize(level.value, spec.step_count);
+            if level.needs_send(step, true, tick) {
+                scalars.insert(i as u32, (level.value, actuator));
+                steps.push((key, step, true));
+            }
+        }
+
+        if scalars.is_empty() {
+            self.count(dev.index(), |c| c.skipped += 1);
+            return;
+        }
+
+        match dev.scalar(scalars, tick.now).await {
+            Ok(()) => self.mark_sent(dev.index(), levels, steps, tick.now),
+            Err(e) => {
+                self.count(dev.index(), |c| c.failed += 1);
+                error!("Failed to send scalar command to {}: {}", dev.name(), e);
             }
         }
     }
 
-    fn has_both(pair: &DevicePair) -> bool {
-        pair.oscillator.is_some() && pair.vibrator.is_some()
+    async fn send_rotate(
+        &self,
+        dev: &OutputDevice,
+        settings: &DeviceSettings,
+        tick: &Tick,
+        levels: &mut HashMap<ActuatorKey, Level>,
+    ) {
+        let specs = dev.actuators(ActuatorKind::Rotate);
+        if specs.is_empty() {
+            return;
+        }
+
+        let mut rotations = HashMap::new();
+        let mut steps = Vec::new();
+        for (i, spec) in specs.iter().enumerate() {
+            let role = settings.role_for(ActuatorKind::Rotate, i as u32, spec.actuator_type);
+            let curve = settings.curve_for(ActuatorKind::Rotate, i as u32).unwrap_or_default();
+            let key = (dev.index(), ActuatorKind::Rotate, i as u32);
+            if is_idle_disabled(role, levels, key) {
+                continue;
+            }
+            let (target, clockwise) = match &tick.replay {
+                Some(values) => {
+                    let (value, clockwise) = values.get(&key).copied().unwrap_or((0.0, true));
+                    (value.min(tick.max_limit), clockwise)
+                }
+                None => (
+                    curve.apply(role_value(role, &tick.output)).min(tick.max_limit) * settings.scale,
+                    role != ActuatorRole::RotateAlternating || tick.output.clockwise,
+                ),
+            };
+
+            let limits = curve.slew_limits(role);
+            let level = update_level(levels, key, target, limits, settings, tick);
+            let step = quantize(level.value, spec.step_count);
+            if level.needs_send(step, clockwise, tick) {
+                rotations.insert(i as u32, (level.value, clockwise));
+                steps.push((key, step, clockwise));
+            }
+        }
+
+        if rotations.is_empty() {
+            self.count(dev.index(), |c| c.skipped += 1);
+            return;
+        }
+
+        match dev.rotate(rotations, tick.now).await {
+            Ok(()) => self.mark_sent(dev.index(), levels, steps, tick.now),
+            Err(e) => {
+                self.count(dev.index(), |c| c.failed += 1);
+                error!("Failed to send rotate command to {}: {}", dev.name(), e);
+            }
+        }
     }
 
-    async fn assign_device(&self, device: Arc<ButtplugClientDevice>) {
-        let mut devices = self.devices.lock().await;
+    /// Values of the running replay at `now`; ends the replay once it has run out.
+    fn replay_values(&self, now: Instant) -> Option<ReplayValues> {
+        let mut replay = self.replay.write().unwrap_or_else(|e| e.into_inner());
+        let values = replay.as_ref()?.values_at(now);
+        if values.is_none()
+            && let Some(finished) = replay.take()
+        {
+            info!("Replay of session {} finished", finished.name);
+        }
+        values
+    }
+
+    /// Script times of the stroke hits passed since the previous tick. Seeks and jumps
+    /// of more than `MAX_BEAT_GAP_MS` report nothing.
+    fn beats_since_last_tick(&self, position_ms: f64, playing: bool) -> Vec<u64> {
+        let mut last = self.last_tick_position.write().unwrap_or_else(|e| e.into_inner());
+        let from = last.replace(position_ms);
+        match from {
+            Some(from) if playing && position_ms > from && position_ms - from <= MAX_BEAT_GAP_MS => {
+                let engine = self.playback.read().unwrap_or_else(|e| e.into_inner());
+                engine.beats_between(from, position_ms).to_vec()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Append the applied output to the running recording, with the stroke hits passed
+    /// since the previous tick.
+    fn record_sample(
+        &self,
+        now: Instant,
+        position_ms: f64,
+        playing: bool,
+        beats: Vec<u64>,
+        actuators: &[AppliedActuator],
+        levels: &HashMap<ActuatorKey, Level>,
+    ) {
+        let mut recorder = self.recorder.write().unwrap_or_else(|e| e.into_inner());
+        let Some(recorder) = recorder.as_mut() else {
+            return;
+        };
+
+        let values = actuators
+            .iter()
+            .map(|a| RecordedValue {
+                device: a.device,
+                kind: a.kind,
+                index: a.index,
+                value: a.value,
+                clockwise: (a.kind == ActuatorKind::Rotate).then(|| {
+                    levels
+                        .get(&(a.device, a.kind, a.index))
+                        .and_then(|l| l.sent)
+                        .is_none_or(|s| s.clockwise)
+                }),
+            })
+            .collect();
+        recorder.push(now, position_ms, playing, beats, values);
+    }
+
+    /// Remember what a successful command delivered, so unchanged values are skipped.
+    fn mark_sent(
+        &self,
+        device_index: u32,
+        levels: &mut HashMap<ActuatorKey, Level>,
+        steps: Vec<(ActuatorKey, u32, bool)>,
+        now: Instant,
+    ) {
+        for (key, step, clockwise) in steps {
+            if let Some(level) = levels.get_mut(&key) {
+                level.sent = Some(SentLevel {
+                    value: level.value,
+                    step,
+                    clockwise,
+                    at: now,
+                });
+            }
+        }
+        self.count(device_index, |c| c.sent += 1);
+    }
 
-        let dominated_actuators: Vec<_> = device
-            .message_attributes()
-            .scalar_cmd()
-            .as_ref()
-            .map(|cmds| cmds.iter().map(|c| c.actuator_type().clone()).collect())
-            .unwrap_or_default();
+    /// Send the upcoming script action to every linear actuator with the position role,
+    /// unless this device already received that exact move.
+    async fn send_linear(&self, dev: &OutputDevice, settings: &DeviceSettings, target: LinearTarget, now: Instant) {
+        let linears: HashMap<u32, (u32, f64)> = dev
+            .actuators(ActuatorKind::Linear)
+            .iter()
+            .enumerate()
+            .filter(|(i, spec)| {
+                settings.role_for(ActuatorKind::Linear, *i as u32, spec.actuator_type) == ActuatorRole::LinearPosition
+            })
+            .map(|(i, _)| (i as u32, (target.duration_ms, target.position)))
+            .collect();
 
-        if dominated_actuators.contains(&ActuatorType::Oscillate) {
-            info!("Assigned oscillator: {}", device.name());
-            devices.oscillator = Some(device.clone());
+        if linears.is_empty() {
+            return;
         }
 
-        if dominated_actuators.contains(&ActuatorType::Vibrate) {
-            info!("Assigned vibrator: {}", device.name());
-            devices.vibrator = Some(device);
+        let key = (target.epoch, target.action_index);
+        let already_sent = self
+            .linear_sent
+            .lock()
+            .await
+            .get(&dev.index())
+            .is_some_and(|(sent_key, _)| *sent_key == key);
+        if already_sent {
+            return;
+        }
+
+        // Only a delivered move counts as sent, so a failed one is retried on the next tick
+        match dev.linear(linears, now).await {
+            Ok(()) => {
+                self.linear_sent.lock().await.insert(dev.index(), (key, target.position));
+                self.count(dev.index(), |c| c.sent += 1);
+            }
+            Err(e) => {
+                self.count(dev.index(), |c| c.failed += 1);
+                error!("Failed to send linear command to {}: {}", dev.name(), e);
+            }
+        }
+    }
+
+    /// See `emergency_stop`.
+    async fn emergency_stop(&self) -> Result<(), String> {
+        if !self.stopped.swap(true, Ordering::SeqCst) {
+            let _ = self.events.send(ManagerEvent::StopState { stopped: true });
+        }
+        self.replay.write().unwrap_or_else(|e| e.into_inner()).take();
+        {
+            let now = Instant::now();
+            let mut engine = self.playback.write().unwrap_or_else(|e| e.into_inner());
+            engine.halt(Duration::ZERO, now);
+            engine.set_manual(None, now);
+        }
+        warn!("Emergency stop triggered");
+
+        // Stop the hardware first, without waiting for the control loop
+        let client = self.client.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let result = match client {
+            Some(client) => client
+                .stop_all_devices()
+                .await
+                .map_err(|e| format!("Failed to stop devices: {e}")),
+            None => Ok(()),
+        };
+
+        // Then wait for an in-flight control tick; the next one sees `stopped` and sends nothing
+        let devices = self.devices.lock().await;
+        self.levels.lock().await.clear();
+        for dev in devices.values().filter(|d| d.is_virtual()) {
+            let _ = dev.stop(Instant::now()).await;
+        }
+        result
+    }
+
+    /// Ramp everything down once clients have been silent for longer than the timeout.
+    fn check_watchdog(&self) {
+        let config = self.config();
+        if config.watchdog_timeout_ms == 0 {
+            return;
+        }
+
+        let idle = self.last_command.read().unwrap_or_else(|e| e.into_inner()).elapsed();
+        if idle < Duration::from_millis(config.watchdog_timeout_ms) {
+            return;
+        }
+
+        let now = Instant::now();
+        let fade = Duration::from_millis(config.watchdog_fade_ms);
+        if let Some(name) = self.halt_replay(fade, now) {
+            warn!(
+                "No client command for {}ms, watchdog stopped the replay of session {}",
+                idle.as_millis(),
+                name
+            );
+        }
+
+        let mut engine = self.playback.write().unwrap_or_else(|e| e.into_inner());
+        if !engine.is_active() {
+            return;
+        }
+
+        let position = engine.position_ms(now);
+        engine.halt(fade, now);
+        *self.watchdog_stop.write().unwrap_or_else(|e| e.into_inner()) = Some(position);
+        warn!(
+            "No client command for {}ms, watchdog stopped playback at {:.0}ms",
+            idle.as_millis(),
+            position
+        );
+    }
+
+    /// Fade out a running replay. Returns its name unless none was running or it was
+    /// already fading.
+    fn halt_replay(&self, fade: Duration, now: Instant) -> Option<String> {
+        let mut replay = self.replay.write().unwrap_or_else(|e| e.into_inner());
+        let replay = replay.as_mut()?;
+        replay.halt(fade, now).then(|| replay.name.clone())
+    }
+
+    fn clear_watchdog_stop(&self) {
+        self.watchdog_stop.write().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    async fn assign_device(&self, device: OutputDevice) {
+        let settings = self.settings_for(&device);
+        for actuator in actuator_infos(&device, &settings, |_, _| None) {
+            info!(
+                "Device {} ({}) {:?} actuator {} [{}] -> {:?}",
+                device.index(),
+                device.name(),
+                actuator.kind,
+                actuator.index,
+                actuator.actuator_type,
+                actuator.role
+            );
+        }
+
+        let index = device.index();
+        self.devices.lock().await.insert(index, device);
+        self.reset_missing_scan();
+        if let Some(info) = self.device_infos().await.into_iter().find(|d| d.index == index) {
+            self.publish(DeviceEvent::DeviceAdded { device: info });
         }
     }
 
     async fn remove_device(&self, device_index: u32) {
         let mut devices = self.devices.lock().await;
+        if let Some(dev) = devices.remove(&device_index) {
+            info!("Device {} ({}) disconnected", device_index, dev.name());
+            self.publish(DeviceEvent::DeviceRemoved { index: device_index });
+        }
+        self.reset_missing_scan();
+        self.linear_sent.lock().await.remove(&device_index);
+        self.levels.lock().await.retain(|(index, _, _), _| *index != device_index);
+        self.telemetry.write().unwrap_or_else(|e| e.into_inner()).remove(&device_index);
+    }
 
-        if devices
-            .oscillator
-            .as_ref()
-            .map(|d| d.index() == device_index)
-            .unwrap_or(false)
-        {
-            info!("Oscillator disconnected");
-            devices.oscillator = None;
+    /// Send a device event to every subscribed client; dropped when nobody listens.
+    fn publish(&self, event: DeviceEvent) {
+        let _ = self.events.send(ManagerEvent::Device(event));
+    }
+
+    /// Read battery and signal strength of every device and warn about low batteries.
+    async fn poll_telemetry(&self) {
+        let devices: Vec<OutputDevice> = self.devices.lock().await.values().cloned().collect();
+        for dev in devices {
+            let battery = dev.battery_level().await.unwrap_or_else(|e| {
+                warn!("Failed to read battery level of {}: {}", dev.name(), e);
+                None
+            });
+            let rssi = dev.rssi_level().await.unwrap_or_else(|e| {
+                warn!("Failed to read signal strength of {}: {}", dev.name(), e);
+                None
+            });
+            self.update_telemetry(&dev, battery, rssi);
         }
+    }
 
-        if devices
-            .vibrator
-            .as_ref()
-            .map(|d| d.index() == device_index)
-            .unwrap_or(false)
-        {
-            info!("Vibrator disconnected");
-            devices.vibrator = None;
+    fn update_telemetry(&self, dev: &OutputDevice, battery: Option<f64>, rssi: Option<i32>) {
+        let config = self.config();
+        let threshold = config.low_battery_percent as f64 / 100.0;
+        let newly_low = {
+            let mut telemetry = self.telemetry.write().unwrap_or_else(|e| e.into_inner());
+            let entry = telemetry.entry(dev.index()).or_default();
+            entry.battery = battery;
+            entry.rssi = rssi;
+            let low = battery.is_some_and(|b| b < threshold);
+            let newly_low = low && !entry.low_battery;
+            entry.low_battery = low;
+            newly_low
+        };
+
+        self.publish(DeviceEvent::Telemetry {
+            index: dev.index(),
+            battery,
+            rssi,
+        });
+
+        let Some(battery) = battery.filter(|_| newly_low) else {
+            return;
+        };
+        let paused = config.low_battery_pause && {
+            let now = Instant::now();
+            let fade = Duration::from_millis(config.watchdog_fade_ms);
+            let replaying = self.halt_replay(fade, now).is_some();
+            let mut engine = self.playback.write().unwrap_or_else(|e| e.into_inner());
+            let active = engine.is_active();
+            if active {
+                engine.halt(fade, now);
+            }
+            active || replaying
+        };
+        warn!(
+            "Battery of {} is low ({:.0}%){}",
+            dev.name(),
+            battery * 100.0,
+            if paused { ", playback stopped" } else { "" }
+        );
+        self.publish(DeviceEvent::LowBattery {
+            index: dev.index(),
+            name: dev.name().to_string(),
+            battery,
+            paused,
+        });
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        *self.connection_state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_connection_state(&self, state: ConnectionState) {
+        let mut guard = self.connection_state.write().unwrap_or_else(|e| e.into_inner());
+        if *guard != state {
+            info!("Intiface connection state: {:?} -> {:?}", *guard, state);
+            *guard = state;
+            self.publish(DeviceEvent::Connection { state });
+        }
+    }
+
+    fn config(&self) -> IntifaceConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Handle client events until the server goes away or a reconnect is requested.
+    async fn run_event_loop(&self, mut events: impl futures::Stream<Item = ButtplugClientEvent> + Unpin) {
+        loop {
+            let event = tokio::select! {
+                event = events.next() => match event {
+                    Some(event) => event,
+                    None => return,
+                },
+                _ = self.reconnect.notified() => {
+                    info!("Output configuration changed, reconnecting");
+                    return;
+                }
+            };
+
+            match event {
+                ButtplugClientEvent::DeviceAdded(dev) => {
+                    self.assign_device(OutputDevice::Intiface(dev)).await;
+                }
+                ButtplugClientEvent::DeviceRemoved(dev) => {
+                    self.remove_device(dev.index()).await;
+                }
+                ButtplugClientEvent::ScanningFinished
+                    if self.connection_state() == ConnectionState::Scanning =>
+                {
+                    self.set_connection_state(ConnectionState::Connected);
+                }
+                ButtplugClientEvent::ServerDisconnect => {
+                    warn!("Intiface server disconnected");
+                    return;
+                }
+                ButtplugClientEvent::PingTimeout => {
+                    warn!("Intiface server ping timeout");
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Serve a single virtual device until the output configuration changes.
+    async fn run_virtual_backend(&self) {
+        let device = Arc::new(VirtualDevice::default());
+        *self.virtual_device.write().unwrap_or_else(|e| e.into_inner()) = Some(device.clone());
+        self.assign_device(OutputDevice::Virtual(device)).await;
+        self.set_connection_state(ConnectionState::Connected);
+        info!("Using the virtual output backend");
+
+        self.reconnect.notified().await;
+
+        self.clear_devices().await;
+        self.virtual_device.write().unwrap_or_else(|e| e.into_inner()).take();
+        self.set_connection_state(ConnectionState::Disconnected);
+        info!("Output configuration changed, leaving the virtual backend");
+    }
+
+    async fn clear_devices(&self) {
+        let removed: Vec<u32> = std::mem::take(&mut *self.devices.lock().await).into_keys().collect();
+        self.reset_missing_scan();
+        self.linear_sent.lock().await.clear();
+        self.levels.lock().await.clear();
+        self.telemetry.write().unwrap_or_else(|e| e.into_inner()).clear();
+        for index in removed {
+            self.publish(DeviceEvent::DeviceRemoved { index });
         }
     }
 
+    /// Start scanning when devices are missing and stop once everything is back.
+    async fn update_scanning(&self) {
+        let Some(client) = self.client.read().unwrap_or_else(|e| e.into_inner()).clone() else {
+            return;
+        };
+
+        let needs_scan = self.needs_scan().await;
+        match self.connection_state() {
+            ConnectionState::Connected if needs_scan => match client.start_scanning().await {
+                Ok(_) => self.set_connection_state(ConnectionState::Scanning),
+                Err(e) => warn!("Scan failed: {}", e),
+            },
+            ConnectionState::Scanning if !needs_scan => {
+                if let Err(e) = client.stop_scanning().await {
+                    warn!("Failed to stop scanning: {}", e);
+                }
+                self.set_connection_state(ConnectionState::Connected);
+            }
+            _ => {}
+        }
+    }
+
+    /// Scan while nothing is connected, or for up to `MISSING_DEVICE_SCAN_LIMIT` while a
+    /// previously configured device with an enabled actuator is missing.
     async fn needs_scan(&self) -> bool {
         let devices = self.devices.lock().await;
-        !Self::has_both(&devices)
+        if devices.is_empty() {
+            return true;
+        }
+        let missing = {
+            let store = self.settings.read().unwrap_or_else(|e| e.into_inner());
+            store
+                .iter()
+                .filter(|(index, settings)| **index != VIRTUAL_DEVICE_INDEX && settings.has_enabled_role())
+                .any(|(index, _)| !devices.contains_key(index))
+        };
+        if !missing {
+            return false;
+        }
+
+        let mut since = self.missing_scan_since.write().unwrap_or_else(|e| e.into_inner());
+        since.get_or_insert_with(Instant::now).elapsed() < MISSING_DEVICE_SCAN_LIMIT
+    }
+
+    fn reset_missing_scan(&self) {
+        self.missing_scan_since.write().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    async fn device_infos(&self) -> Vec<DeviceInfo> {
+        let devices = self.devices.lock().await;
+        let levels = self.levels.lock().await;
+        let linear_sent = self.linear_sent.lock().await;
+        let counters = self.command_counters.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let telemetry = self.telemetry.read().unwrap_or_else(|e| e.into_inner()).clone();
+        devices
+            .values()
+            .map(|dev| {
+                let settings = self.settings_for(dev);
+                let last_value = |kind, i| last_sent_value(&levels, &linear_sent, dev.index(), kind, i);
+                let readings = telemetry.get(&dev.index()).copied().unwrap_or_default();
+                DeviceInfo {
+                    index: dev.index(),
+                    name: dev.name().to_string(),
+                    is_virtual: dev.is_virtual(),
+                    connected: dev.is_connected(),
+                    battery: readings.battery,
+                    rssi: readings.rssi,
+                    scale: settings.scale,
+                    soft_start_ms: settings.soft_start_ms,
+                    actuators: actuator_infos(dev, &settings, last_value),
+                    commands: counters.get(&dev.index()).copied().unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Scalar, linear and rotate actuators, each indexed within its own command family.
+fn actuator_infos(
+    dev: &OutputDevice,
+    settings: &DeviceSettings,
+    last_value: impl Fn(ActuatorKind, u32) -> Option<f64>,
+) -> Vec<ActuatorInfo> {
+    let last_value = &last_value;
+    [ActuatorKind::Scalar, ActuatorKind::Linear, ActuatorKind::Rotate]
+        .into_iter()
+        .flat_map(|kind| {
+            dev.actuators(kind).into_iter().enumerate().map(move |(i, spec)| {
+                let role = settings.role_for(kind, i as u32, spec.actuator_type);
+                ActuatorInfo {
+                    kind,
+                    index: i as u32,
+                    actuator_type: spec.actuator_type,
+                    step_count: spec.step_count,
+                    role,
+                    last_value: last_value(kind, i as u32),
+                    curve: settings.curve_for(kind, i as u32).map(|c| c.resolved(role)),
+                    description: spec.description,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Value an actuator last received: its speed, or the position for linear actuators.
+fn last_sent_value(
+    levels: &HashMap<ActuatorKey, Level>,
+    linear_sent: &HashMap<u32, (LinearMoveKey, f64)>,
+    device_index: u32,
+    kind: ActuatorKind,
+    actuator_index: u32,
+) -> Option<f64> {
+    match kind {
+        ActuatorKind::Linear => linear_sent.get(&device_index).map(|(_, position)| *position),
+        _ => levels
+            .get(&(device_index, kind, actuator_index))
+            .and_then(|l| l.sent)
+            .map(|s| s.value),
+    }
+}
+
+/// Slew the actuator at `key` towards `target` within its `(rise, fall)` limits, apply
+/// the device's soft start and store the new value.
+fn update_level(
+    levels: &mut HashMap<ActuatorKey, Level>,
+    key: ActuatorKey,
+    target: f64,
+    limits: (f64, f64),
+    settings: &DeviceSettings,
+    tick: &Tick,
+) -> Level {
+    let previous = levels.get(&key).copied();
+    let base = slew(target, previous.map(|l| (l.base, l.at)), limits, tick.now);
+    let level = Level {
+        base,
+        value: soft_start(base, settings.soft_start_ms, tick),
+        at: tick.now,
+        sent: previous.and_then(|l| l.sent),
+    };
+    levels.insert(key, level);
+    level
+}
+
+/// Whether the actuator at `key` is disabled and known to be at zero, so it gets no
+/// commands at all. One that was running when it got disabled is still sent its stop.
+fn is_idle_disabled(role: ActuatorRole, levels: &HashMap<ActuatorKey, Level>, key: ActuatorKey) -> bool {
+    role == ActuatorRole::Disabled
+        && levels
+            .get(&key)
+            .and_then(|l| l.sent)
+            .is_none_or(|sent| sent.value == 0.0)
+}
+
+/// Step of `value` on an actuator with `step_count` levels; devices without a reported
+/// resolution are treated as having 100.
+fn quantize(value: f64, step_count: u32) -> u32 {
+    let steps = if step_count == 0 { 100 } else { step_count };
+    (value.clamp(0.0, 1.0) * steps as f64).round() as u32
+}
+
+/// Move from the previously computed value (or zero) towards `target`, at most by the
+/// `(rise, fall)` rates per second; a rate of 0 is unlimited.
+fn slew(
+    target: f64,
+    previous: Option<(f64, Instant)>,
+    (rise, fall): (f64, f64),
+    now: Instant,
+) -> f64 {
+    let target = target.clamp(0.0, 1.0);
+    let (prev, at) = previous.unwrap_or((0.0, now));
+    let dt = now.saturating_duration_since(at).as_secs_f64();
+    let rate = if target > prev { rise } else { fall };
+    if rate <= 0.0 {
+        return target;
+    }
+
+    let max_step = rate * dt;
+    (prev + (target - prev).clamp(-max_step, max_step)).clamp(0.0, 1.0)
+}
+
+/// Cap `value` by the ramp from zero to full output over `soft_start_ms` after play, seek
+/// or re-arm. The cap does not feed back into the slew base, so output continues at
+/// its rate-limited value once the ramp has passed it.
+fn soft_start(value: f64, soft_start_ms: u64, tick: &Tick) -> f64 {
+    if soft_start_ms == 0 {
+        return value;
+    }
+    let elapsed_ms = tick.now.saturating_duration_since(tick.ramp_start).as_secs_f64() * 1000.0;
+    value.min((elapsed_ms / soft_start_ms as f64).clamp(0.0, 1.0))
+}
+
+fn role_value(role: ActuatorRole, output: &PlaybackOutput) -> f64 {
+    match role {
+        ActuatorRole::PrimaryStroke | ActuatorRole::RotateAlternating | ActuatorRole::RotateContinuous => output.stroke,
+        ActuatorRole::BeatVibration => output.beat_vibration,
+        ActuatorRole::RateVibration => output.rate_vibration,
+        ActuatorRole::LinearPosition | ActuatorRole::Disabled => 0.0,
     }
 }
 
 pub async fn initialize() -> Result<(), ButtplugClientError> {
-    let mgr = DeviceManager::new();
+    let settings = device_settings::read_settings_file().await.unwrap_or_else(|e| {
+        error!("Failed to load device settings, using defaults: {}", e);
+        DeviceSettingsStore::new()
+    });
+    let config = IntifaceConfig::from_env();
+    info!("Intiface configuration: {:?}", config);
+    let mgr = DeviceManager::new(config, settings, output_sink::sinks_from_env());
     MANAGER.set(mgr.clone()).ok();
 
-    let client = Arc::new(ButtplugClient::new("Video Player"));
-
-    // Connect with retries
-    let c = client.clone();
+    // Connection supervisor
+    let m = mgr.clone();
     tokio::spawn(async move {
+        let mut delay = Duration::from_secs(m.config().reconnect_delay_secs);
         loop {
-            let connector = new_json_ws_client_connector(SERVER_URL);
-            match c.connect(connector).await {
+            if m.config().backend == OutputBackend::Virtual {
+                m.run_virtual_backend().await;
+                continue;
+            }
+
+            m.set_connection_state(ConnectionState::Connecting);
+            let client = Arc::new(ButtplugClient::new("Video Player"));
+            let events = client.event_stream();
+
+            let server_url = m.config().server_url;
+            let connector = new_json_ws_client_connector(&server_url);
+            match client.connect(connector).await {
                 Ok(_) => {
-                    info!("Connected to Intiface server at {}", SERVER_URL);
-                    break;
+                    info!("Connected to Intiface server at {}", server_url);
+                    delay = Duration::from_secs(m.config().reconnect_delay_secs);
+                    *m.client.write().unwrap_or_else(|e| e.into_inner()) = Some(client.clone());
+                    m.set_connection_state(ConnectionState::Connected);
+
+                    m.run_event_loop(events).await;
+
+                    m.client.write().unwrap_or_else(|e| e.into_inner()).take();
+                    let _ = client.disconnect().await;
+                    m.clear_devices().await;
+                    m.set_connection_state(ConnectionState::Disconnected);
+                    warn!("Lost connection to Intiface, reconnecting in {}s", delay.as_secs());
                 }
                 Err(e) => {
+                    m.set_connection_state(ConnectionState::Disconnected);
                     warn!(
                         "Failed to connect to Intiface (retrying in {}s): {}",
-                        RECONNECT_DELAY_SECS, e
+                        delay.as_secs(),
+                        e
                     );
-                    tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
                 }
             }
-        }
-    });
 
-    // Event stream handler
-    let m = mgr.clone();
-    let mut events = client.event_stream();
-    tokio::spawn(async move {
-        while let Some(event) = events.next().await {
-            match event {
-                ButtplugClientEvent::DeviceAdded(dev) => {
-                    let dev: Arc<ButtplugClientDevice> = dev;
-                    m.assign_device(dev).await;
-                }
-                ButtplugClientEvent::DeviceRemoved(dev) => {
-                    m.remove_device(dev.index()).await;
+            // A URL change skips the remaining backoff and retries right away
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {
+                    let config = m.config();
+                    delay = (delay * 2)
+                        .max(Duration::from_secs(config.reconnect_delay_secs))
+                        .min(Duration::from_secs(config.max_reconnect_delay_secs));
                 }
-                ButtplugClientEvent::ServerDisconnect => {
-                    warn!("Intiface server disconnected");
+                _ = m.reconnect.notified() => {
+                    delay = Duration::from_secs(m.config().reconnect_delay_secs);
                 }
-                _ => {}
             }
         }
     });
@@ -193,47 +1212,134 @@ pub async fn initialize() -> Result<(), ButtplugClientError> {
     let m = mgr.clone();
     tokio::spawn(async move {
         loop {
-            m.send_commands().await;
-            tokio::time::sleep(Duration::from_millis(CONTROL_INTERVAL_MS)).await;
+            m.check_watchdog();
+            m.send_commands(Instant::now()).await;
+            tokio::time::sleep(Duration::from_millis(m.config().control_interval_ms)).await;
         }
     });
 
-    // Periodic re-scan when devices are missing
+    // Battery and signal strength polling
     let m = mgr.clone();
-    let c = client.clone();
     tokio::spawn(async move {
         loop {
-            tokio::time::sleep(Duration::from_secs(SCAN_INTERVAL_SECS)).await;
-            if m.needs_scan().await {
-                if let Err(e) = c.start_scanning().await {
-                    warn!("Scan failed: {}", e);
-                }
+            let interval = m.config().telemetry_interval_secs;
+            if interval > 0 {
+                m.poll_telemetry().await;
             }
+            tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+        }
+    });
+
+    // Periodic re-scan when devices are missing
+    let m = mgr.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(m.config().scan_interval_secs)).await;
+            m.update_scanning().await;
         }
     });
 
     Ok(())
 }
 
-pub fn set_oscillate(value: f64) {
+fn with_playback(f: impl FnOnce(&mut PlaybackEngine, Instant)) {
     if let Some(m) = MANAGER.get() {
-        m.oscillate_intensity
-            .store(value.clamp(0.0, 1.0), std::sync::atomic::Ordering::Relaxed);
+        let mut engine = m.playback.write().unwrap_or_else(|e| e.into_inner());
+        f(&mut engine, Instant::now());
     }
 }
 
-pub fn set_vibrate(value: f64) {
+/// Load the timeline for `source` (the funscript path the client is playing).
+pub fn load_playback(source: String, actions: Vec<Action>, intensity: Vec<Action>) {
+    with_playback(|engine, now| engine.load(source, actions, intensity, now));
+}
+
+pub fn play(at_ms: f64) {
     if let Some(m) = MANAGER.get() {
-        m.vibrate_intensity
-            .store(value.clamp(0.0, 1.0), std::sync::atomic::Ordering::Relaxed);
+        m.clear_watchdog_stop();
+        if let Some(replay) = m.replay.write().unwrap_or_else(|e| e.into_inner()).take() {
+            info!("Playback resumed, replay of session {} cancelled", replay.name);
+        }
     }
+    with_playback(|engine, now| engine.play(at_ms, now));
+}
+
+pub fn pause(at_ms: f64) {
+    with_playback(|engine, now| engine.pause(at_ms, now));
+}
+
+/// Pause at the current extrapolated position, for callers that do not know the video time.
+pub fn pause_here() {
+    with_playback(|engine, now| {
+        let at = engine.position_ms(now);
+        engine.pause(at, now);
+    });
+}
+
+pub fn playback_status() -> Option<PlaybackStatus> {
+    let m = MANAGER.get()?;
+    let engine = m.playback.read().unwrap_or_else(|e| e.into_inner());
+    Some(PlaybackStatus {
+        source: engine.source().map(str::to_string),
+        playing: engine.is_playing(),
+        position_ms: engine.position_ms(Instant::now()),
+    })
+}
+
+/// Stroke hits of the loaded script in the `horizon_ms` after `from_ms`.
+pub fn upcoming_beats(from_ms: f64, horizon_ms: f64) -> Vec<u64> {
+    let Some(m) = MANAGER.get() else {
+        return Vec::new();
+    };
+    let engine = m.playback.read().unwrap_or_else(|e| e.into_inner());
+    engine.beats_between(from_ms, from_ms + horizon_ms).to_vec()
+}
+
+pub fn seek(at_ms: f64) {
+    with_playback(|engine, now| engine.seek(at_ms, now));
+}
+
+pub fn sync_position(at_ms: f64) {
+    with_playback(|engine, now| engine.sync(at_ms, now));
+}
+
+/// Override the stroke output with a fixed value (calibration), or release it with `None`.
+pub fn set_manual_oscillate(value: Option<f64>) {
+    if let Some(m) = MANAGER.get()
+        && value.is_some()
+    {
+        m.clear_watchdog_stop();
+    }
+    with_playback(|engine, now| engine.set_manual(value, now));
+}
+
+/// Record that a client is alive. Called for every valid WebSocket command.
+pub fn touch_watchdog() {
+    if let Some(m) = MANAGER.get() {
+        *m.last_command.write().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+}
+
+/// Script position (ms) at which the watchdog stopped playback, if it has tripped
+/// and playback was not resumed since.
+pub fn watchdog_stop_position() -> Option<f64> {
+    MANAGER
+        .get()
+        .and_then(|m| *m.watchdog_stop.read().unwrap_or_else(|e| e.into_inner()))
 }
 
 pub fn set_active_calibration_points(points: Vec<(f64, f64)>) {
+    if let Some(m) = MANAGER.get()
+        && let Ok(mut guard) = m.active_calibration_points.write()
+    {
+        *guard = points;
+    }
+}
+
+/// Name of the calibration profile the active points came from, kept for session recordings.
+pub fn set_active_calibration_name(name: Option<String>) {
     if let Some(m) = MANAGER.get() {
-        if let Ok(mut guard) = m.active_calibration_points.write() {
-            *guard = points;
-        }
+        *m.active_calibration_name.write().unwrap_or_else(|e| e.into_inner()) = name;
     }
 }
 
@@ -248,8 +1354,625 @@ pub fn get_active_calibration_points() -> Vec<(f64, f64)> {
     }
 }
 
+/// Set the global ceiling applied to every scalar and rotate actuator on top of its own limit.
 pub fn set_max_limit(limit: f64) {
     if let Some(m) = MANAGER.get() {
-        m.max_limit.store(limit.clamp(0.0, 1.0), std::sync::atomic::Ordering::Relaxed);
+        let limit = limit.clamp(0.0, 1.0);
+        if m.max_limit.swap(limit, Ordering::Relaxed) != limit {
+            let _ = m.events.send(ManagerEvent::MaxLimit { limit });
+        }
+    }
+}
+
+pub fn get_max_limit() -> f64 {
+    MANAGER
+        .get()
+        .map(|m| m.max_limit.load(Ordering::Relaxed))
+        .unwrap_or(0.0)
+}
+
+/// Set the limit of one actuator, or of every scalar and rotate actuator of the device
+/// when `actuator` is `None`, and persist it.
+pub async fn set_device_max_limit(
+    device_index: u32,
+    actuator: Option<(ActuatorKind, u32)>,
+    limit: f64,
+) -> Result<DeviceInfo, String> {
+    let m = MANAGER.get().ok_or("Device manager not initialized")?;
+    if let Some((ActuatorKind::Linear, _)) = actuator {
+        return Err("Linear actuators have no output limit".to_string());
+    }
+
+    let device = m
+        .devices
+        .lock()
+        .await
+        .get(&device_index)
+        .cloned()
+        .ok_or_else(|| format!("Device {device_index} is not connected"))?;
+
+    let limit_update = ActuatorCurveUpdate {
+        limit: Some(limit),
+        ..Default::default()
+    };
+    let curves_of = |kind: ActuatorKind| -> HashMap<u32, ActuatorCurveUpdate> {
+        match actuator {
+            Some((k, i)) if k == kind => HashMap::from([(i, limit_update)]),
+            Some(_) => HashMap::new(),
+            None => (0..device.actuators(kind).len() as u32).map(|i| (i, limit_update)).collect(),
+        }
+    };
+
+    let update = DeviceSettingsUpdate {
+        curves: Some(curves_of(ActuatorKind::Scalar)),
+        rotate_curves: Some(curves_of(ActuatorKind::Rotate)),
+        ..Default::default()
+    };
+    update_device_settings(device_index, update).await
+}
+
+/// Halt playback, zero every device through Intiface and latch the stop until `rearm`.
+pub async fn emergency_stop() -> Result<(), String> {
+    let m = MANAGER.get().ok_or("Device manager not initialized")?;
+    m.emergency_stop().await
+}
+
+/// Release a latched emergency stop. Playback stays paused until the next play, and
+/// output soft-starts again from zero.
+pub fn rearm() {
+    if let Some(m) = MANAGER.get()
+        && m.stopped.swap(false, Ordering::SeqCst)
+    {
+        with_playback(|engine, now| engine.restart_ramp(now));
+        let _ = m.events.send(ManagerEvent::StopState { stopped: false });
+        info!("Emergency stop released");
+    }
+}
+
+pub fn is_stopped() -> bool {
+    MANAGER
+        .get()
+        .map(|m| m.stopped.load(Ordering::SeqCst))
+        .unwrap_or(false)
+}
+
+pub fn get_connection_state() -> ConnectionState {
+    MANAGER
+        .get()
+        .map(|m| m.connection_state())
+        .unwrap_or(ConnectionState::Disconnected)
+}
+
+pub fn get_intiface_config() -> IntifaceConfig {
+    MANAGER.get().map(|m| m.config()).unwrap_or_else(IntifaceConfig::from_env)
+}
+
+/// Apply a partial configuration update. Timing changes are picked up by the loops on
+/// their next iteration; a new server URL or backend makes the supervisor reconnect immediately.
+pub fn update_intiface_config(update: IntifaceConfigUpdate) -> Result<IntifaceConfig, String> {
+    let m = MANAGER.get().ok_or("Device manager not initialized")?;
+
+    let (updated, reconnect) = {
+        let mut config = m.config.write().unwrap_or_else(|e| e.into_inner());
+        let updated = config.merged(update)?;
+        let reconnect = updated.server_url != config.server_url || updated.backend != config.backend;
+        *config = updated.clone();
+        (updated, reconnect)
+    };
+
+    info!("Intiface configuration updated: {:?}", updated);
+    if reconnect {
+        m.reconnect.notify_one();
+    }
+    Ok(updated)
+}
+
+/// Current state and recorded commands (from sequence number `since_seq`) of the virtual
+/// device, or `None` when the virtual backend is not active.
+pub fn virtual_device_snapshot(since_seq: u64) -> Option<(VirtualState, Vec<RecordedCommand>)> {
+    let m = MANAGER.get()?;
+    let device = m.virtual_device.read().unwrap_or_else(|e| e.into_inner()).clone()?;
+    Some((device.state(), device.commands_since(since_seq)))
+}
+
+/// Command counters of every device seen since startup, keyed by device index.
+pub fn command_counters() -> BTreeMap<u32, CommandCounters> {
+    match MANAGER.get() {
+        Some(m) => m.command_counters.read().unwrap_or_else(|e| e.into_inner()).clone(),
+        None => BTreeMap::new(),
+    }
+}
+
+/// Subscribe to device events, stop state and max limit changes, or `None` before the
+/// device manager is initialized.
+pub fn subscribe_events() -> Option<broadcast::Receiver<ManagerEvent>> {
+    MANAGER.get().map(|m| m.events.subscribe())
+}
+
+/// Follow the values the devices last received, updated after every control tick.
+pub fn subscribe_applied_output() -> Option<watch::Receiver<AppliedOutput>> {
+    MANAGER.get().map(|m| m.applied.subscribe())
+}
+
+pub async fn list_devices() -> Vec<DeviceInfo> {
+    match MANAGER.get() {
+        Some(m) => m.device_infos().await,
+        None => Vec::new(),
+    }
+}
+
+/// Update the settings of a connected device and persist the settings store.
+pub async fn update_device_settings(device_index: u32, update: DeviceSettingsUpdate) -> Result<DeviceInfo, String> {
+    let m = MANAGER.get().ok_or("Device manager not initialized")?;
+
+    let device = m
+        .devices
+        .lock()
+        .await
+        .get(&device_index)
+        .cloned()
+        .ok_or_else(|| format!("Device {device_index} is not connected"))?;
+
+    let mut settings = m.settings_for(&device);
+    let updates = [
+        (ActuatorKind::Scalar, update.roles),
+        (ActuatorKind::Linear, update.linear_roles),
+        (ActuatorKind::Rotate, update.rotate_roles),
+    ];
+    for (kind, roles) in updates {
+        let Some(roles) = roles else { continue };
+        let actuator_count = device.actuators(kind).len();
+        if let Some(bad) = roles.keys().find(|&&i| i as usize >= actuator_count) {
+            return Err(format!("Device {device_index} has no {kind:?} actuator {bad}"));
+        }
+        if let Some((i, role)) = roles.iter().find(|(_, role)| !role.supports(kind)) {
+            return Err(format!("Role {role:?} is not supported by {kind:?} actuator {i}"));
+        }
+        settings.roles_for_mut(kind).extend(roles);
+    }
+    let curve_updates = [
+        (ActuatorKind::Scalar, update.curves),
+        (ActuatorKind::Rotate, update.rotate_curves),
+    ];
+    for (kind, curves) in curve_updates {
+        let Some(curves) = curves else { continue };
+        let actuator_count = device.actuators(kind).len();
+        for (i, curve_update) in curves {
+            if i as usize >= actuator_count {
+                return Err(format!("Device {device_index} has no {kind:?} actuator {i}"));
+            }
+            let current = settings.curve_for(kind, i).unwrap_or_default();
+            let merged = current
+                .merged(curve_update)
+                .map_err(|e| format!("{kind:?} actuator {i}: {e}"))?;
+            if let Some(stored) = settings.curves_for_mut(kind) {
+                stored.insert(i, merged);
+            }
+        }
+    }
+    if let Some(scale) = update.scale {
+        settings.scale = scale.clamp(0.0, 1.0);
+    }
+    if let Some(ms) = update.soft_start_ms {
+        settings.soft_start_ms = ms;
+    }
+
+    let snapshot = {
+        let mut store = m.settings.write().unwrap_or_else(|e| e.into_inner());
+        store.insert(device_index, settings);
+        store.clone()
+    };
+    device_settings::write_settings_file(&snapshot).await?;
+
+    m.device_infos()
+        .await
+        .into_iter()
+        .find(|d| d.index == device_index)
+        .ok_or_else(|| format!("Device {device_index} is not connected"))
+}
+
+/// Start recording the applied output. `meta` describes what is being played; the
+/// connected devices and the active calibration are filled in here.
+pub async fn start_recording(mut meta: SessionMeta) -> Result<(), String> {
+    let m = MANAGER.get().ok_or("Device manager not initialized")?;
+    meta.devices = m
+        .devices
+        .lock()
+        .await
+        .values()
+        .map(|d| RecordedDevice {
+            index: d.index(),
+            name: d.name().to_string(),
+        })
+        .collect();
+    meta.calibration_points = get_active_calibration_points();
+    meta.calibration_profile = m.active_calibration_name.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+    let mut recorder = m.recorder.write().unwrap_or_else(|e| e.into_inner());
+    if recorder.is_some() {
+        return Err("A recording is already running".to_string());
+    }
+    *recorder = Some(Recorder::new(meta, Instant::now()));
+    info!("Session recording started");
+    Ok(())
+}
+
+/// Stop the running recording and hand it back for saving.
+pub fn stop_recording() -> Option<SessionRecording> {
+    let m = MANAGER.get()?;
+    let recorder = m.recorder.write().unwrap_or_else(|e| e.into_inner()).take()?;
+    info!("Session recording stopped after {} samples", recorder.sample_count());
+    Some(recorder.finish(Instant::now()))
+}
+
+/// Number of samples recorded so far, if a recording is running.
+pub fn recording_samples() -> Option<usize> {
+    let m = MANAGER.get()?;
+    m.recorder
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(Recorder::sample_count)
+}
+
+/// Play `recording` back from `from_ms` in place of the playback engine, which is paused.
+/// Cancelled by play and by the emergency stop; faded out by the watchdog and low battery.
+pub async fn start_replay(name: String, recording: SessionRecording, from_ms: u64) -> Result<(), String> {
+    let m = MANAGER.get().ok_or("Device manager not initialized")?;
+    if is_stopped() {
+        return Err("Emergency stop is active; re-arm before replaying".to_string());
+    }
+
+    let devices: Vec<(u32, String)> = m
+        .devices
+        .lock()
+        .await
+        .values()
+        .map(|d| (d.index(), d.name().to_string()))
+        .collect();
+    let now = Instant::now();
+    {
+        let mut engine = m.playback.write().unwrap_or_else(|e| e.into_inner());
+        let position = engine.position_ms(now);
+        engine.pause(position, now);
+        engine.restart_ramp(now);
+    }
+
+    // The replay counts as a client command, so the watchdog gives it its full timeout
+    *m.last_command.write().unwrap_or_else(|e| e.into_inner()) = now;
+    info!("Replaying session {name} from {from_ms}ms");
+    *m.replay.write().unwrap_or_else(|e| e.into_inner()) = Some(Replay::new(name, recording, from_ms, &devices, now));
+    Ok(())
+}
+
+/// Stop a running replay. Returns false if none was running.
+pub fn stop_replay() -> bool {
+    MANAGER
+        .get()
+        .and_then(|m| m.replay.write().unwrap_or_else(|e| e.into_inner()).take())
+        .is_some()
+}
+
+pub fn replay_status() -> Option<ReplayStatus> {
+    let m = MANAGER.get()?;
+    let replay = m.replay.read().unwrap_or_else(|e| e.into_inner());
+    replay.as_ref().map(|r| ReplayStatus {
+        name: r.name.clone(),
+        position_ms: r.position_ms(Instant::now()),
+        duration_ms: r.duration().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buttplug::{
+        playback::TRANSITION_DURATION, session_recording::RecordedSample, virtual_device::VirtualCommand,
+    };
+
+    const SOFT_START_MS: u64 = 2000;
+    const TICK_MS: u64 = 50;
+    /// Interval between the actions of the test script.
+    const STROKE_MS: u64 = 250;
+
+    /// Manager with the virtual device registered, its settings adjusted by `configure`.
+    async fn virtual_manager(configure: impl FnOnce(&mut DeviceSettings)) -> (Arc<DeviceManager>, Arc<VirtualDevice>) {
+        let device = Arc::new(VirtualDevice::default());
+        let mut settings = DeviceSettings::new(device.name());
+        settings.soft_start_ms = 0;
+        configure(&mut settings);
+
+        let store = DeviceSettingsStore::from([(VIRTUAL_DEVICE_INDEX, settings)]);
+        let mgr = DeviceManager::new(IntifaceConfig::default(), store, Vec::new());
+        mgr.assign_device(OutputDevice::Virtual(device.clone())).await;
+        (mgr, device)
+    }
+
+    /// Load a binary script (0 and 100 every `STROKE_MS`, hits at every second action)
+    /// at full intensity and play it from 0. Returns the play time.
+    fn play_script(mgr: &DeviceManager) -> Instant {
+        let actions: Vec<Action> = (0..40)
+            .map(|i| Action {
+                at: i * STROKE_MS,
+                pos: (i % 2 * 100) as f64,
+            })
+            .collect();
+        let intensity = vec![Action { at: 0, pos: 100.0 }, Action { at: 10_000, pos: 100.0 }];
+        let start = Instant::now();
+        let mut engine = mgr.playback.write().unwrap();
+        engine.load("test.funscript".to_string(), actions, intensity, start);
+        engine.play(0.0, start);
+        start
+    }
+
+    /// Run the control ticks `ticks` (numbered from the play time), calling `after` with
+    /// the tick time in ms after each.
+    async fn run_ticks(
+        mgr: &DeviceManager,
+        start: Instant,
+        ticks: std::ops::RangeInclusive<u64>,
+        mut after: impl FnMut(u64),
+    ) {
+        for i in ticks {
+            mgr.send_commands(start + Duration::from_millis(i * TICK_MS)).await;
+            after(i * TICK_MS);
+        }
+    }
+
+    /// Recorded commands with their time relative to the play time, given that the
+    /// first command was sent on the first tick.
+    fn timed_commands(device: &VirtualDevice) -> Vec<(u64, VirtualCommand)> {
+        let commands = device.commands_since(0);
+        let base = commands[0].at_ms - TICK_MS;
+        commands.into_iter().map(|c| (c.at_ms - base, c.command)).collect()
+    }
+
+    fn scalar_values(commands: &[(u64, VirtualCommand)], index: u32) -> Vec<(u64, f64)> {
+        commands
+            .iter()
+            .filter_map(|(at, command)| match command {
+                VirtualCommand::Scalar { values } => values.get(&index).map(|v| (*at, *v)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn virtual_device_receives_soft_started_capped_output() {
+        // No rise limit on the oscillator, so only the soft start shapes the ramp
+        let (mgr, device) = virtual_manager(|settings| {
+            settings.soft_start_ms = SOFT_START_MS;
+            let unlimited = ActuatorCurve {
+                max_rise_per_sec: Some(0.0),
+                ..Default::default()
+            };
+            settings.curves.insert(0, unlimited);
+        })
+        .await;
+        let max_limit = mgr.max_limit.load(Ordering::Relaxed);
+        let start = play_script(&mgr);
+
+        let mut previous = 0.0;
+        run_ticks(&mgr, start, 1..=60, |elapsed_ms| {
+            let oscillate = device.state().scalar.get(&0).copied().unwrap_or(0.0);
+            let ramp = elapsed_ms as f64 / SOFT_START_MS as f64;
+            assert!(oscillate <= ramp + 1e-9, "{oscillate} above the soft start ramp {ramp} at {elapsed_ms}ms");
+            assert!(oscillate <= max_limit + 1e-9, "{oscillate} above the max limit at {elapsed_ms}ms");
+            assert!(oscillate >= previous, "output dropped from {previous} to {oscillate} at {elapsed_ms}ms");
+            previous = oscillate;
+        })
+        .await;
+
+        // Timestamps come from the tick that sent the command
+        let commands = timed_commands(&device);
+        assert!(commands.iter().all(|(at, _)| at % TICK_MS == 0 && *at <= 60 * TICK_MS));
+        let oscillate = scalar_values(&commands, 0);
+        assert!(oscillate.len() > 5, "expected a gradual ramp, got {oscillate:?}");
+        for (at, value) in &oscillate {
+            let ramp = *at as f64 / SOFT_START_MS as f64;
+            assert!((value - ramp.min(max_limit)).abs() < 1e-9, "{value} at {at}ms");
+        }
+        assert!((previous - max_limit).abs() < 1e-9, "ramp ended at {previous}");
+
+        mgr.emergency_stop().await.unwrap();
+        let last = device.commands_since(0).pop().unwrap();
+        assert_eq!(last.command, VirtualCommand::Stop);
+        assert!(device.state().scalar.values().all(|v| *v == 0.0));
+        assert!(device.state().rotate.values().all(|(v, _)| *v == 0.0));
+
+        // Nothing is sent while the stop is latched
+        run_ticks(&mgr, start, 61..=70, |_| {}).await;
+        assert!(device.commands_since(last.seq + 1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn linear_rotate_and_rate_vibration_follow_their_roles() {
+        let (mgr, device) = virtual_manager(|_| {}).await;
+        let max_limit = mgr.max_limit.load(Ordering::Relaxed);
+        let start = play_script(&mgr);
+        run_ticks(&mgr, start, 1..=40, |_| {}).await;
+        let commands = timed_commands(&device);
+
+        // One move per action, sent on the first tick after the previous action
+        let linear: Vec<_> = commands
+            .iter()
+            .filter_map(|(at, command)| match command {
+                VirtualCommand::Linear { values } => Some((*at, values[&0])),
+                _ => None,
+            })
+            .collect();
+        let expected: Vec<_> = std::iter::once(TICK_MS)
+            .chain((1..=8).map(|i| i * STROKE_MS))
+            .map(|at| {
+                let next = at / STROKE_MS + 1;
+                (at, ((next * STROKE_MS - at) as u32, (next % 2) as f64))
+            })
+            .collect();
+        assert_eq!(linear, expected);
+
+        // Alternating rotation turns clockwise on the up-strokes
+        let rotate: Vec<_> = commands
+            .iter()
+            .filter_map(|(at, command)| match command {
+                VirtualCommand::Rotate { values } => Some((*at, values[&0])),
+                _ => None,
+            })
+            .collect();
+        assert!(rotate.iter().any(|(_, (_, clockwise))| *clockwise));
+        assert!(rotate.iter().any(|(_, (_, clockwise))| !*clockwise));
+        for (at, (speed, clockwise)) in &rotate {
+            assert_eq!(*clockwise, (at / STROKE_MS).is_multiple_of(2), "direction at {at}ms");
+            assert!(*speed <= max_limit + 1e-9);
+        }
+
+        // Rate vibration follows the fade-in after play, past the dead zone
+        let vibrate = scalar_values(&commands, 1);
+        assert!(!vibrate.is_empty());
+        for (at, value) in &vibrate {
+            let level = (*at as f64 / TRANSITION_DURATION.as_millis() as f64).min(1.0);
+            let expected = ((level - 0.03).max(0.0) * 1.5).min(max_limit);
+            assert!((value - expected).abs() < 1e-9, "{value} instead of {expected} at {at}ms");
+        }
+        assert!((vibrate.last().unwrap().1 - max_limit).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn beat_vibration_pulses_and_disabled_actuator_gets_nothing() {
+        let (mgr, device) = virtual_manager(|settings| {
+            settings.roles.insert(0, ActuatorRole::BeatVibration);
+            settings.roles.insert(1, ActuatorRole::Disabled);
+        })
+        .await;
+        let max_limit = mgr.max_limit.load(Ordering::Relaxed);
+        let start = play_script(&mgr);
+
+        let mut values = Vec::new();
+        run_ticks(&mgr, start, 1..=60, |elapsed_ms| {
+            values.push((elapsed_ms, device.state().scalar.get(&0).copied().unwrap_or(0.0)));
+        })
+        .await;
+
+        // Hits every second action; full pulse on each once faded in, decayed before the next
+        let beat_interval = 2 * STROKE_MS;
+        for (at, value) in &values {
+            if *at >= 1000 && at % beat_interval == 0 {
+                assert!((value - max_limit).abs() < 1e-9, "{value} on the hit at {at}ms");
+            }
+            if *at >= 1000 && at % beat_interval == beat_interval - TICK_MS {
+                assert!(*value < 0.1, "{value} just before the hit at {at}ms");
+            }
+        }
+
+        let commands = timed_commands(&device);
+        assert!(!scalar_values(&commands, 0).is_empty());
+        assert!(scalar_values(&commands, 1).is_empty(), "the disabled vibrator received commands");
+
+        // Disabling a running actuator sends it one stop and then nothing
+        mgr.settings
+            .write()
+            .unwrap()
+            .get_mut(&VIRTUAL_DEVICE_INDEX)
+            .unwrap()
+            .roles
+            .insert(0, ActuatorRole::Disabled);
+        let seq = device.commands_since(0).last().unwrap().seq + 1;
+        run_ticks(&mgr, start, 61..=80, |_| {}).await;
+        let after: Vec<_> = device
+            .commands_since(seq)
+            .into_iter()
+            .filter_map(|c| match c.command {
+                VirtualCommand::Scalar { values } => Some(values),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(after, vec![BTreeMap::from([(0, 0.0)])]);
+    }
+
+    #[tokio::test]
+    async fn scan_for_missing_devices_is_bounded() {
+        let (mgr, device) = virtual_manager(|_| {}).await;
+        let mut disabled = DeviceSettings::new("Disabled device");
+        disabled.roles.insert(0, ActuatorRole::Disabled);
+        mgr.settings.write().unwrap().insert(3, disabled);
+        assert!(!mgr.needs_scan().await, "a device with every role disabled is not searched for");
+
+        mgr.settings.write().unwrap().insert(4, DeviceSettings::new("Missing device"));
+        assert!(mgr.needs_scan().await);
+
+        *mgr.missing_scan_since.write().unwrap() = Some(Instant::now() - MISSING_DEVICE_SCAN_LIMIT);
+        assert!(!mgr.needs_scan().await, "scanning stops once the limit has passed");
+
+        // A device arriving starts another bounded search
+        mgr.assign_device(OutputDevice::Virtual(device)).await;
+        assert!(mgr.needs_scan().await);
+    }
+
+    /// Replay of a constant 0.5 on the oscillator of the virtual device, ten seconds long.
+    fn start_test_replay(mgr: &DeviceManager, device: &VirtualDevice, now: Instant) {
+        let value = RecordedValue {
+            device: VIRTUAL_DEVICE_INDEX,
+            kind: ActuatorKind::Scalar,
+            index: 0,
+            value: 0.5,
+            clockwise: None,
+        };
+        let sample = |t_ms| RecordedSample {
+            t_ms,
+            position_ms: t_ms as f64,
+            playing: true,
+            beats: Vec::new(),
+            values: vec![value.clone()],
+        };
+        let recording = SessionRecording {
+            meta: SessionMeta {
+                devices: vec![RecordedDevice {
+                    index: VIRTUAL_DEVICE_INDEX,
+                    name: device.name().to_string(),
+                }],
+                ..Default::default()
+            },
+            samples: vec![sample(0), sample(10_000)],
+        };
+        let devices = [(VIRTUAL_DEVICE_INDEX, device.name().to_string())];
+        *mgr.replay.write().unwrap() = Some(Replay::new("test".to_string(), recording, 0, &devices, now));
+    }
+
+    fn replayed_value(mgr: &DeviceManager, at: Instant) -> Option<f64> {
+        let values = mgr.replay_values(at)?;
+        Some(values[&(VIRTUAL_DEVICE_INDEX, ActuatorKind::Scalar, 0)].0)
+    }
+
+    #[tokio::test]
+    async fn watchdog_and_low_battery_fade_out_a_replay() {
+        let (mgr, device) = virtual_manager(|_| {}).await;
+        let config = mgr.config();
+        let fade = Duration::from_millis(config.watchdog_fade_ms);
+
+        // Clients still active: the replay keeps running
+        start_test_replay(&mgr, &device, Instant::now());
+        mgr.check_watchdog();
+        assert_eq!(replayed_value(&mgr, Instant::now()), Some(0.5));
+
+        // Clients silent for longer than the timeout: the replay fades out and ends
+        let silent = Duration::from_millis(config.watchdog_timeout_ms + 1);
+        *mgr.last_command.write().unwrap() = Instant::now().checked_sub(silent).unwrap();
+        let halted = Instant::now();
+        mgr.check_watchdog();
+        let half = replayed_value(&mgr, halted + fade / 2).unwrap();
+        assert!(half > 0.0 && half < 0.5, "{half} halfway through the fade");
+        assert_eq!(replayed_value(&mgr, halted + fade + Duration::from_millis(1)), None);
+        assert!(mgr.replay.read().unwrap().is_none());
+        assert_eq!(*mgr.watchdog_stop.read().unwrap(), None);
+
+        // A low battery fades it out the same way when configured to pause
+        mgr.config.write().unwrap().low_battery_pause = true;
+        *mgr.last_command.write().unwrap() = Instant::now();
+        start_test_replay(&mgr, &device, Instant::now());
+        let halted = Instant::now();
+        let dev = mgr.devices.lock().await[&VIRTUAL_DEVICE_INDEX].clone();
+        mgr.update_telemetry(&dev, Some(0.05), None);
+        let half = replayed_value(&mgr, halted + fade / 2).unwrap();
+        assert!(half > 0.0 && half < 0.5, "{half} halfway through the fade");
+        assert_eq!(replayed_value(&mgr, halted + fade + Duration::from_millis(1)), None);
     }
 }
@@ -0,0 +1,363 @@
+// src/buttplug/device_settings.rs
+
+//! Persisted per-device settings
+//!
+//! Stores the role assigned to each actuator, its output curve (limit, floor, gamma) and
+//! rise/fall rate limits, plus a per-device output scale and soft start applied by the
+//! control loop. Scalar (vibrate/oscillate), linear and rotate actuators are indexed
+//! separately by Buttplug, so their roles and curves are kept in separate maps. Settings
+//! are kept in a JSON file (.device_settings.json) under FUNSCRIPT_SHARE_PATH and are
+//! keyed by Buttplug device index. The device name is stored alongside so settings are
+//! only applied when the same device reappears under that index.
+
+use buttplug::core::message::ActuatorType;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env, io::ErrorKind, path::PathBuf};
+use tokio::fs;
+
+const DEVICE_SETTINGS_FILE_NAME: &str = ".device_settings.json";
+const MIN_GAMMA: f64 = 0.1;
+const MAX_GAMMA: f64 = 10.0;
+
+/// Rise/fall limits of motor roles without a stored value (fraction of full scale per
+/// second). Vibration roles are unlimited so beat pulses keep their shape.
+const DEFAULT_MOTOR_MAX_RISE_PER_SEC: f64 = 0.5;
+const DEFAULT_MOTOR_MAX_FALL_PER_SEC: f64 = 2.0;
+
+/// Buttplug command family an actuator is driven through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActuatorKind {
+    /// ScalarCmd (vibrate, oscillate, constrict, ...)
+    Scalar,
+    /// LinearCmd (position + duration)
+    Linear,
+    /// RotateCmd (speed + direction)
+    Rotate,
+}
+
+/// What an actuator is driven by during playback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActuatorRole {
+    /// Follows the stroke intensity curve (subject to the max limit).
+    PrimaryStroke,
+    /// Pulses on each stroke hit and decays before the next one.
+    BeatVibration,
+    /// Continuous vibration scaled with the stroke intensity.
+    RateVibration,
+    /// Moves to the upcoming positions of the raw script (linear actuators only).
+    LinearPosition,
+    /// Speed follows the stroke intensity, direction flips on every up/down stroke
+    /// of the raw script (rotate actuators only).
+    RotateAlternating,
+    /// Speed follows the stroke intensity, always clockwise (rotate actuators only).
+    RotateContinuous,
+    /// Held at zero.
+    Disabled,
+}
+
+impl ActuatorRole {
+    /// Role used for actuators that have no stored assignment.
+    pub fn default_for(kind: ActuatorKind, actuator: ActuatorType) -> Self {
+        match (kind, actuator) {
+            (ActuatorKind::Linear, _) => ActuatorRole::LinearPosition,
+            (ActuatorKind::Rotate, _) => ActuatorRole::RotateAlternating,
+            (ActuatorKind::Scalar, ActuatorType::Oscillate) => ActuatorRole::PrimaryStroke,
+            (ActuatorKind::Scalar, ActuatorType::Vibrate) => ActuatorRole::RateVibration,
+            (ActuatorKind::Scalar, _) => ActuatorRole::Disabled,
+        }
+    }
+
+    /// Whether an actuator of `kind` can be driven with this role.
+    pub fn supports(self, kind: ActuatorKind) -> bool {
+        match self {
+            ActuatorRole::Disabled => true,
+            ActuatorRole::LinearPosition => kind == ActuatorKind::Linear,
+            ActuatorRole::RotateAlternating | ActuatorRole::RotateContinuous => kind == ActuatorKind::Rotate,
+            _ => kind == ActuatorKind::Scalar,
+        }
+    }
+
+    /// Rise and fall limits used when the actuator's curve stores none.
+    pub fn default_slew_limits(self) -> (f64, f64) {
+        match self {
+            ActuatorRole::PrimaryStroke
+            | ActuatorRole::RotateAlternating
+            | ActuatorRole::RotateContinuous => (DEFAULT_MOTOR_MAX_RISE_PER_SEC, DEFAULT_MOTOR_MAX_FALL_PER_SEC),
+            _ => (0.0, 0.0),
+        }
+    }
+}
+
+/// Mapping from the value a role produces to the value sent to a scalar or rotate
+/// actuator. Non-zero input is shaped with `gamma`, lifted onto `floor .. 1.0` and then
+/// capped at `limit`; zero stays zero so the motor can still stop. The rise/fall limits
+/// then bound how fast the sent value may change.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ActuatorCurve {
+    /// Highest value ever sent (0.0 .. 1.0).
+    #[serde(default = "default_curve_limit")]
+    pub limit: f64,
+    /// Lowest non-zero value sent, the point where the motor actually starts moving.
+    #[serde(default)]
+    pub floor: f64,
+    /// Exponent applied to the input; above 1 softens low values, below 1 lifts them.
+    #[serde(default = "default_curve_gamma")]
+    pub gamma: f64,
+    /// Maximum output increase per second (fraction of full scale, 0 = unlimited);
+    /// `None` uses the role default.
+    #[serde(default)]
+    pub max_rise_per_sec: Option<f64>,
+    /// Maximum output decrease per second (fraction of full scale, 0 = unlimited);
+    /// `None` uses the role default.
+    #[serde(default)]
+    pub max_fall_per_sec: Option<f64>,
+}
+
+impl Default for ActuatorCurve {
+    fn default() -> Self {
+        Self {
+            limit: default_curve_limit(),
+            floor: 0.0,
+            gamma: default_curve_gamma(),
+            max_rise_per_sec: None,
+            max_fall_per_sec: None,
+        }
+    }
+}
+
+impl ActuatorCurve {
+    pub fn apply(&self, value: f64) -> f64 {
+        if value <= 0.0 {
+            return 0.0;
+        }
+        let shaped = value.min(1.0).powf(self.gamma);
+        (self.floor + (1.0 - self.floor) * shaped).min(self.limit)
+    }
+
+    /// Rise and fall limits for an actuator with `role`.
+    pub fn slew_limits(&self, role: ActuatorRole) -> (f64, f64) {
+        let (rise, fall) = role.default_slew_limits();
+        (self.max_rise_per_sec.unwrap_or(rise), self.max_fall_per_sec.unwrap_or(fall))
+    }
+
+    /// The curve with the role defaults filled in for unset rise/fall limits.
+    pub fn resolved(&self, role: ActuatorRole) -> Self {
+        let (rise, fall) = self.slew_limits(role);
+        Self {
+            max_rise_per_sec: Some(rise),
+            max_fall_per_sec: Some(fall),
+            ..*self
+        }
+    }
+
+    /// Apply a partial update, returning the validated result without modifying `self`.
+    pub fn merged(&self, update: ActuatorCurveUpdate) -> Result<Self, String> {
+        let merged = Self {
+            limit: update.limit.unwrap_or(self.limit),
+            floor: update.floor.unwrap_or(self.floor),
+            gamma: update.gamma.unwrap_or(self.gamma),
+            max_rise_per_sec: update.max_rise_per_sec.or(self.max_rise_per_sec),
+            max_fall_per_sec: update.max_fall_per_sec.or(self.max_fall_per_sec),
+        };
+        if !(0.0..=1.0).contains(&merged.limit) {
+            return Err(format!("limit must be between 0 and 1 (got {})", merged.limit));
+        }
+        if !(0.0..=1.0).contains(&merged.floor) {
+            return Err(format!("floor must be between 0 and 1 (got {})", merged.floor));
+        }
+        if merged.floor > merged.limit {
+            return Err(format!(
+                "floor ({}) must not be above the limit ({})",
+                merged.floor, merged.limit
+            ));
+        }
+        if !(MIN_GAMMA..=MAX_GAMMA).contains(&merged.gamma) {
+            return Err(format!(
+                "gamma must be between {MIN_GAMMA} and {MAX_GAMMA} (got {})",
+                merged.gamma
+            ));
+        }
+        for (name, rate) in [
+            ("max_rise_per_sec", merged.max_rise_per_sec),
+            ("max_fall_per_sec", merged.max_fall_per_sec),
+        ] {
+            if let Some(rate) = rate
+                && !(rate.is_finite() && rate >= 0.0)
+            {
+                return Err(format!("{name} must be 0 or above (got {rate})"));
+            }
+        }
+        Ok(merged)
+    }
+}
+
+/// Partial curve update; `None` fields keep their current value.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct ActuatorCurveUpdate {
+    pub limit: Option<f64>,
+    pub floor: Option<f64>,
+    pub gamma: Option<f64>,
+    pub max_rise_per_sec: Option<f64>,
+    pub max_fall_per_sec: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSettings {
+    pub name: String,
+    /// Roles keyed by actuator index within the device's ScalarCmd attributes.
+    #[serde(default)]
+    pub roles: HashMap<u32, ActuatorRole>,
+    /// Roles keyed by actuator index within the device's LinearCmd attributes.
+    #[serde(default)]
+    pub linear_roles: HashMap<u32, ActuatorRole>,
+    /// Roles keyed by actuator index within the device's RotateCmd attributes.
+    #[serde(default)]
+    pub rotate_roles: HashMap<u32, ActuatorRole>,
+    /// Output curves keyed by ScalarCmd actuator index.
+    #[serde(default)]
+    pub curves: HashMap<u32, ActuatorCurve>,
+    /// Output curves keyed by RotateCmd actuator index.
+    #[serde(default)]
+    pub rotate_curves: HashMap<u32, ActuatorCurve>,
+    /// Multiplier (0.0 .. 1.0) applied to every scalar and rotate value sent to this device.
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    /// Length of the ramp from zero to full output after play, seek or re-arm.
+    #[serde(default = "default_soft_start_ms")]
+    pub soft_start_ms: u64,
+}
+
+fn default_curve_limit() -> f64 {
+    1.0
+}
+
+fn default_curve_gamma() -> f64 {
+    1.0
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn default_soft_start_ms() -> u64 {
+    2000
+}
+
+/// Partial settings update; `None` fields keep their current value. Scale is
+/// normalized (0.0 .. 1.0 of full scale).
+#[derive(Debug, Clone, Default)]
+pub struct DeviceSettingsUpdate {
+    pub roles: Option<HashMap<u32, ActuatorRole>>,
+    pub linear_roles: Option<HashMap<u32, ActuatorRole>>,
+    pub rotate_roles: Option<HashMap<u32, ActuatorRole>>,
+    pub curves: Option<HashMap<u32, ActuatorCurveUpdate>>,
+    pub rotate_curves: Option<HashMap<u32, ActuatorCurveUpdate>>,
+    pub scale: Option<f64>,
+    pub soft_start_ms: Option<u64>,
+}
+
+impl DeviceSettings {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            roles: HashMap::new(),
+            linear_roles: HashMap::new(),
+            rotate_roles: HashMap::new(),
+            curves: HashMap::new(),
+            rotate_curves: HashMap::new(),
+            scale: default_scale(),
+            soft_start_ms: default_soft_start_ms(),
+        }
+    }
+
+    pub fn role_for(&self, kind: ActuatorKind, actuator_index: u32, actuator: ActuatorType) -> ActuatorRole {
+        self.roles_for(kind)
+            .get(&actuator_index)
+            .copied()
+            .unwrap_or_else(|| ActuatorRole::default_for(kind, actuator))
+    }
+
+    /// Whether the device drives anything: a stored role other than `Disabled`, or no
+    /// stored roles at all so the defaults apply.
+    pub fn has_enabled_role(&self) -> bool {
+        let roles = [&self.roles, &self.linear_roles, &self.rotate_roles];
+        roles.iter().all(|r| r.is_empty())
+            || roles.iter().flat_map(|r| r.values()).any(|role| *role != ActuatorRole::Disabled)
+    }
+
+    pub fn roles_for(&self, kind: ActuatorKind) -> &HashMap<u32, ActuatorRole> {
+        match kind {
+            ActuatorKind::Scalar => &self.roles,
+            ActuatorKind::Linear => &self.linear_roles,
+            ActuatorKind::Rotate => &self.rotate_roles,
+        }
+    }
+
+    pub fn roles_for_mut(&mut self, kind: ActuatorKind) -> &mut HashMap<u32, ActuatorRole> {
+        match kind {
+            ActuatorKind::Scalar => &mut self.roles,
+            ActuatorKind::Linear => &mut self.linear_roles,
+            ActuatorKind::Rotate => &mut self.rotate_roles,
+        }
+    }
+
+    /// Output curve of an actuator, or `None` for linear actuators, which are driven by
+    /// positions rather than intensities.
+    pub fn curve_for(&self, kind: ActuatorKind, actuator_index: u32) -> Option<ActuatorCurve> {
+        let curves = self.curves_for(kind)?;
+        Some(curves.get(&actuator_index).copied().unwrap_or_default())
+    }
+
+    pub fn curves_for(&self, kind: ActuatorKind) -> Option<&HashMap<u32, ActuatorCurve>> {
+        match kind {
+            ActuatorKind::Scalar => Some(&self.curves),
+            ActuatorKind::Linear => None,
+            ActuatorKind::Rotate => Some(&self.rotate_curves),
+        }
+    }
+
+    pub fn curves_for_mut(&mut self, kind: ActuatorKind) -> Option<&mut HashMap<u32, ActuatorCurve>> {
+        match kind {
+            ActuatorKind::Scalar => Some(&mut self.curves),
+            ActuatorKind::Linear => None,
+            ActuatorKind::Rotate => Some(&mut self.rotate_curves),
+        }
+    }
+}
+
+/// Device settings keyed by Buttplug device index.
+pub type DeviceSettingsStore = HashMap<u32, DeviceSettings>;
+
+fn settings_store_path() -> PathBuf {
+    let base_dir = env::var("FUNSCRIPT_SHARE_PATH").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(base_dir).join(DEVICE_SETTINGS_FILE_NAME)
+}
+
+pub async fn read_settings_file() -> Result<DeviceSettingsStore, String> {
+    let path = settings_store_path();
+
+    match fs::read_to_string(&path).await {
+        Ok(raw_json) => serde_json::from_str(&raw_json)
+            .map_err(|e| format!("Failed to parse device settings JSON: {e}")),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(format!("Failed to read device settings file {:?}: {e}", path)),
+    }
+}
+
+pub async fn write_settings_file(store: &DeviceSettingsStore) -> Result<(), String> {
+    let path = settings_store_path();
+    let json = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("Failed to serialize device settings: {e}"))?;
+
+    if let Some(parent_dir) = path.parent() {
+        fs::create_dir_all(parent_dir)
+            .await
+            .map_err(|e| format!("Failed to create directory {:?}: {e}", parent_dir))?;
+    }
+
+    fs::write(&path, json)
+        .await
+        .map_err(|e| format!("Failed to write device settings file {:?}: {e}", path))
+}
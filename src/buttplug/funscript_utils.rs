@@ -11,16 +11,38 @@
 //!
 //! Conventions and units:
 //! - Time is expressed in milliseconds (u64).
-//! - Position values are floating point in the range 0.0 .. 100.0.
-//! - Many helpers assume the input funscript uses "binary" extremes (0 or 100) when
-//!   deriving speed-based intensity. Functions validate and document when this is required.
+//! - Position values are floating point in the range 0.0 .. 100.0, with 100 at the top.
+//!   `parse_funscript` normalises scripts authored with `inverted: true` or on a
+//!   0 .. `range` scale into that convention; `FunscriptData::denormalized_position`
+//!   converts back when a script is rewritten.
+//! - Speed-based intensity works on any positions: a full 0 -> 100 -> 0 stroke is one
+//!   beat, and partial strokes count as a fraction of a beat that depends on their depth
+//!   (see `STROKE_DEPTH_WEIGHT`), so they share the same BPM/intensity scale.
 //! - Intensity values returned by processing functions are in the same 0.0 .. 100.0 range.
+//! - The intensity curve generation is tuned by `IntensityParams`; the built-in presets
+//!   trade responsiveness against smoothness.
+//! - BPM is mapped to intensity through the base table (`bpm_table`), which starts at
+//!   `DEFAULT_BPM_TO_INTENSITY` and can be replaced at runtime; its last point is the
+//!   100% ceiling.
 
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::cmp::min;
+use std::{cmp::min, sync::RwLock};
 
 pub const INTENSITY_STEP_MS: u64 = 50;
 pub const INTENSITY_WINDOW_MS: u64 = 500;
+pub const INTENSITY_MAX_RISE_PER_SEC: f64 = 40.0;
+pub const INTENSITY_SMOOTHING: f64 = 0.6;
+pub const INTENSITY_MERGE_GAP_MS: u64 = 200;
+
+const MIN_INTENSITY_STEP_MS: u64 = 10;
+const MAX_INTENSITY_WINDOW_MS: u64 = 10_000;
+
+/// How much a partial stroke's depth lowers its BPM: 0.0 rates strokes by tempo alone,
+/// 1.0 purely by travelled distance (velocity). Full strokes are unaffected.
+pub const STROKE_DEPTH_WEIGHT: f64 = 0.5;
+/// Strokes shallower than this (position units) are jitter and do not count.
+pub const MIN_STROKE_DEPTH: f64 = 5.0;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Action {
@@ -43,6 +65,106 @@ pub struct FunscriptData {
     pub metadata: Option<serde_json::Value>,
 }
 
+/// Tuning of the intensity curve generation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntensityParams {
+    /// Spacing of the curve points.
+    pub step_ms: u64,
+    /// Stroke speed is measured from `window_ms` before to `window_ms` after each point.
+    pub window_ms: u64,
+    /// Fastest rise of the curve, in intensity percent per second.
+    pub max_rise_per_sec: f64,
+    /// How quickly the curve follows drops (0.0 never, 1.0 immediately).
+    pub smoothing: f64,
+    /// Actions at the same position closer than this are merged before measuring.
+    pub merge_gap_ms: u64,
+}
+
+impl Default for IntensityParams {
+    fn default() -> Self {
+        Self {
+            step_ms: INTENSITY_STEP_MS,
+            window_ms: INTENSITY_WINDOW_MS,
+            max_rise_per_sec: INTENSITY_MAX_RISE_PER_SEC,
+            smoothing: INTENSITY_SMOOTHING,
+            merge_gap_ms: INTENSITY_MERGE_GAP_MS,
+        }
+    }
+}
+
+/// Partial update of `IntensityParams`; missing fields keep their current value.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IntensityParamsUpdate {
+    pub step_ms: Option<u64>,
+    pub window_ms: Option<u64>,
+    pub max_rise_per_sec: Option<f64>,
+    pub smoothing: Option<f64>,
+    pub merge_gap_ms: Option<u64>,
+}
+
+impl IntensityParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.step_ms < MIN_INTENSITY_STEP_MS {
+            return Err(format!("step_ms must be at least {MIN_INTENSITY_STEP_MS}"));
+        }
+        if self.window_ms == 0 || self.window_ms > MAX_INTENSITY_WINDOW_MS {
+            return Err(format!("window_ms must be between 1 and {MAX_INTENSITY_WINDOW_MS}"));
+        }
+        if !self.max_rise_per_sec.is_finite() || self.max_rise_per_sec <= 0.0 {
+            return Err("max_rise_per_sec must be above 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.smoothing) {
+            return Err("smoothing must be between 0 and 1".to_string());
+        }
+        if self.merge_gap_ms > MAX_INTENSITY_WINDOW_MS {
+            return Err(format!("merge_gap_ms must not exceed {MAX_INTENSITY_WINDOW_MS}"));
+        }
+        Ok(())
+    }
+
+    /// Apply a partial update, returning the validated result without modifying `self`.
+    pub fn merged(&self, update: IntensityParamsUpdate) -> Result<Self, String> {
+        let merged = Self {
+            step_ms: update.step_ms.unwrap_or(self.step_ms),
+            window_ms: update.window_ms.unwrap_or(self.window_ms),
+            max_rise_per_sec: update.max_rise_per_sec.unwrap_or(self.max_rise_per_sec),
+            smoothing: update.smoothing.unwrap_or(self.smoothing),
+            merge_gap_ms: update.merge_gap_ms.unwrap_or(self.merge_gap_ms),
+        };
+        merged.validate()?;
+        Ok(merged)
+    }
+}
+
+/// Presets that are always available: the defaults, a "responsive" curve that follows
+/// tempo changes quickly and a "smooth" one that evens them out.
+pub fn builtin_intensity_presets() -> Vec<(&'static str, IntensityParams)> {
+    vec![
+        ("default", IntensityParams::default()),
+        (
+            "responsive",
+            IntensityParams {
+                step_ms: 50,
+                window_ms: 300,
+                max_rise_per_sec: 120.0,
+                smoothing: 0.9,
+                merge_gap_ms: 100,
+            },
+        ),
+        (
+            "smooth",
+            IntensityParams {
+                step_ms: 100,
+                window_ms: 1000,
+                max_rise_per_sec: 20.0,
+                smoothing: 0.3,
+                merge_gap_ms: 300,
+            },
+        ),
+    ]
+}
+
 fn default_version() -> String {
     "1.0".into()
 }
@@ -50,6 +172,39 @@ fn default_range() -> u32 {
     100
 }
 
+impl FunscriptData {
+    /// Scale factor from the script's own 0 .. `range` positions to 0 .. 100. A range of
+    /// zero is treated as the default.
+    fn range_scale(&self) -> f64 {
+        if self.range == 0 { 1.0 } else { 100.0 / self.range as f64 }
+    }
+
+    /// Rewrite the positions to 0 .. 100 with 100 at the top and reset `inverted` and
+    /// `range` to match.
+    pub fn normalize(&mut self) {
+        let scale = self.range_scale();
+        for action in &mut self.actions {
+            let pos = (action.pos * scale).clamp(0.0, 100.0);
+            action.pos = if self.inverted { 100.0 - pos } else { pos };
+        }
+        self.inverted = false;
+        self.range = default_range();
+    }
+
+    /// Convert a normalised position into this script's `inverted` / `range` convention.
+    pub fn denormalized_position(&self, pos: f64) -> f64 {
+        let pos = if self.inverted { 100.0 - pos } else { pos };
+        pos / self.range_scale()
+    }
+}
+
+/// Parse funscript JSON and normalise its positions (see `FunscriptData::normalize`).
+pub fn parse_funscript(content: &str) -> Result<FunscriptData, String> {
+    let mut data: FunscriptData = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    data.normalize();
+    Ok(data)
+}
+
 impl Default for FunscriptData {
     fn default() -> Self {
         Self {
@@ -62,9 +217,9 @@ impl Default for FunscriptData {
     }
 }
 
-/// Piecewise-linear BPM-to-intensity calibration table.
+/// Default piecewise-linear BPM-to-intensity base table, measured on a Hismith.
 /// Each entry is (bpm_threshold, intensity_percent).
-pub const BPM_TO_INTENSITY: [(f64, f64); 11] = [
+pub const DEFAULT_BPM_TO_INTENSITY: [(f64, f64); 11] = [
     (0.0, 0.0),
     (42.0, 10.0),
     (66.0, 20.0),
@@ -78,19 +233,71 @@ pub const BPM_TO_INTENSITY: [(f64, f64); 11] = [
     (270.0, 100.0),
 ];
 
-#[derive(Debug, Clone, Serialize)]
+/// Base table in use, replaced through `set_bpm_table`.
+static BPM_TABLE: Lazy<RwLock<Vec<(f64, f64)>>> = Lazy::new(|| RwLock::new(DEFAULT_BPM_TO_INTENSITY.to_vec()));
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BpmIntensityPoint {
     pub bpm: f64,
     pub intensity: f64,
 }
 
 pub fn get_bpm_intensity_mapping() -> Vec<BpmIntensityPoint> {
-    BPM_TO_INTENSITY
-        .iter()
-        .map(|&(bpm, intensity)| BpmIntensityPoint { bpm, intensity })
+    bpm_table()
+        .into_iter()
+        .map(|(bpm, intensity)| BpmIntensityPoint { bpm, intensity })
         .collect()
 }
 
+/// Current base table as (bpm, intensity) points.
+pub fn bpm_table() -> Vec<(f64, f64)> {
+    BPM_TABLE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// A base table must start at 0 BPM / 0%, end at 100% and rise strictly in both BPM and
+/// intensity, so it can be inverted by `intensity_to_bpm`.
+pub fn validate_bpm_table(points: &[BpmIntensityPoint]) -> Result<(), String> {
+    if points.len() < 2 {
+        return Err("The table needs at least two points".to_string());
+    }
+    if points.iter().any(|p| !p.bpm.is_finite() || !p.intensity.is_finite()) {
+        return Err("BPM and intensity must be numbers".to_string());
+    }
+    if points[0].bpm != 0.0 || points[0].intensity != 0.0 {
+        return Err("The first point must be 0 BPM at 0%".to_string());
+    }
+    if points.last().is_some_and(|p| p.intensity != 100.0) {
+        return Err("The last point must be at 100%".to_string());
+    }
+    if let Some(pair) = points
+        .windows(2)
+        .find(|pair| pair[1].bpm <= pair[0].bpm || pair[1].intensity <= pair[0].intensity)
+    {
+        return Err(format!(
+            "Points must rise in BPM and intensity ({} BPM / {}% is followed by {} BPM / {}%)",
+            pair[0].bpm, pair[0].intensity, pair[1].bpm, pair[1].intensity
+        ));
+    }
+    Ok(())
+}
+
+/// Replace the base table after validating it.
+pub fn set_bpm_table(points: &[BpmIntensityPoint]) -> Result<(), String> {
+    validate_bpm_table(points)?;
+    *BPM_TABLE.write().unwrap_or_else(|e| e.into_inner()) = points.iter().map(|p| (p.bpm, p.intensity)).collect();
+    Ok(())
+}
+
+/// BPM of the 100% ceiling of the base table.
+fn max_table_bpm() -> f64 {
+    BPM_TABLE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .last()
+        .map(|p| p.0)
+        .unwrap_or(0.0)
+}
+
 pub fn calculate_intensity_stats(samples: &[Action]) -> (f64, f64) {
     if samples.is_empty() {
         return (0.0, 0.0);
@@ -147,7 +354,7 @@ pub fn generate_funscript_actions_from_taps(taps: &[u64]) -> Vec<Action> {
     }
 
     actions.push(Action { at: last + 500, pos: 0.0 });
-    if actions.first().map_or(false, |a| a.at > 0) {
+    if actions.first().is_some_and(|a| a.at > 0) {
         actions.insert(0, Action { at: 0, pos: 0.0 });
     }
 
@@ -211,7 +418,7 @@ fn bpm_to_intensity(bpm: f64) -> f64 {
         return 0.0;
     }
 
-    let table = &BPM_TO_INTENSITY;
+    let table = BPM_TABLE.read().unwrap_or_else(|e| e.into_inner());
     if bpm >= table.last().unwrap().0 {
         return table.last().unwrap().1;
     }
@@ -227,104 +434,96 @@ fn bpm_to_intensity(bpm: f64) -> f64 {
     0.0
 }
 
-/// Sum absolute position changes within [win_start, win_end], convert to BPM,
-/// then map to intensity. Inserts interpolated boundary points.
-fn window_intensity(actions: &[Action], win_start: u64, win_end: u64, cal_points: &[(f64, f64)]) -> f64 {
+/// Depth of the stroke (run of moves in one direction) each segment `i -> i + 1` of
+/// `actions` belongs to. Holds between strokes have depth 0.
+pub fn stroke_depths(actions: &[Action]) -> Vec<f64> {
+    let mut depths = vec![0.0; actions.len().saturating_sub(1)];
+    let mut start = 0;
+
+    while start < depths.len() {
+        let direction = (actions[start + 1].pos - actions[start].pos).signum();
+        let mut end = start + 1;
+        if actions[start + 1].pos != actions[start].pos {
+            while end < depths.len() && (actions[end + 1].pos - actions[end].pos).signum() == direction {
+                end += 1;
+            }
+            let depth = (actions[end].pos - actions[start].pos).abs();
+            depths[start..end].fill(depth);
+        }
+        start = end;
+    }
+    depths
+}
+
+/// Beats travelled along segments within [win_start, win_end], as BPM. A stroke of depth
+/// `d` counts as half a beat scaled by `(d / 100) ^ STROKE_DEPTH_WEIGHT`, spread over its
+/// segments by distance.
+fn window_bpm(actions: &[Action], depths: &[f64], win_start: u64, win_end: u64) -> f64 {
     if actions.is_empty() || win_end <= win_start {
         return 0.0;
     }
 
-    // Find the index range of actions that fall within or straddle the window.
-    // before_idx: last action at or before win_start
-    // after_idx:  first action at or after win_end
-    let before_idx = actions.iter().rposition(|a| a.at <= win_start);
-    let after_idx = actions.iter().position(|a| a.at >= win_end);
-
-    // Build a small working list with interpolated boundary points.
-    let mut window_actions: Vec<Action> = Vec::new();
-
-    // Interpolated start-boundary point
-    let start_pos = lerp_position(
-        before_idx.map(|i| &actions[i]),
-        actions.iter().find(|a| a.at > win_start),
-        win_start,
-    );
-    window_actions.push(Action {
-        at: win_start,
-        pos: start_pos,
-    });
-
-    // All actions strictly inside the window
-    for action in actions
-        .iter()
-        .filter(|a| a.at > win_start && a.at < win_end)
-    {
-        window_actions.push(action.clone());
-    }
-
-    // Interpolated end-boundary point
-    let end_pos = lerp_position(
-        actions.iter().rev().find(|a| a.at < win_end),
-        after_idx.map(|i| &actions[i]),
-        win_end,
-    );
-    window_actions.push(Action {
-        at: win_end,
-        pos: end_pos,
-    });
+    // Segments that overlap the window; positions are interpolated at the window edges
+    let first = actions.partition_point(|a| a.at <= win_start).saturating_sub(1);
+    let mut beats = 0.0;
+    for (i, pair) in actions.windows(2).enumerate().skip(first) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if a.at >= win_end {
+            break;
+        }
+        let depth = depths[i];
+        if b.at <= win_start || depth < MIN_STROKE_DEPTH {
+            continue;
+        }
 
-    // Total absolute position change across the window
-    let total_change: f64 = window_actions
-        .windows(2)
-        .map(|pair| (pair[1].pos - pair[0].pos).abs())
-        .sum();
+        let from = lerp_position(Some(a), Some(b), a.at.max(win_start));
+        let to = lerp_position(Some(a), Some(b), b.at.min(win_end));
+        let stroke_fraction = (to - from).abs() / depth;
+        beats += stroke_fraction * 0.5 * (depth / 100.0).powf(STROKE_DEPTH_WEIGHT);
+    }
 
     let duration_sec = (win_end - win_start) as f64 / 1000.0;
-    if duration_sec <= 0.0 {
-        return 0.0;
-    }
+    beats * (60.0 / duration_sec)
+}
 
-    // Each full 0→100→0 stroke is 200 position-units and equals one "beat".
-    // BPM = (total_change / 200) / (duration_sec / 60)
-    //     = total_change * 60 / (200 * duration_sec)
-    //     = total_change * 300.0 / (1000.0 * duration_sec)   [when duration is in ms]
-    let bpm = (total_change / 200.0) * (60.0 / duration_sec);
-    let raw_intensity = bpm_to_intensity(bpm);
+/// BPM within [win_start, win_end] (see `window_bpm`) mapped to intensity.
+fn window_intensity(
+    actions: &[Action],
+    depths: &[f64],
+    win_start: u64,
+    win_end: u64,
+    cal_points: &[(f64, f64)],
+) -> f64 {
+    let raw_intensity = bpm_to_intensity(window_bpm(actions, depths, win_start, win_end));
     get_calibrated_intensity(raw_intensity, cal_points)
 }
 
-/// Convert binary (0/100) funscript actions into a smoothed intensity curve.
+/// Convert funscript actions into a smoothed intensity curve.
 ///
 /// Returns evenly-spaced actions where `pos` represents intensity (0..100).
 ///
-/// Returns an empty `Vec` if the input is not a binary script (positions must
-/// be 0 or 100) or contains fewer than 2 actions.
-pub fn actions_to_intensity_curve(actions: &[Action], cal_points: &[(f64, f64)]) -> Vec<Action> {
-    let step_ms = INTENSITY_STEP_MS;
-    let window_ms = INTENSITY_WINDOW_MS;
-    
-    if actions.len() < 2 {
-        return Vec::new();
-    }
+/// Returns an empty `Vec` if the input contains fewer than 2 actions.
+pub fn actions_to_intensity_curve(
+    actions: &[Action],
+    cal_points: &[(f64, f64)],
+    params: &IntensityParams,
+) -> Vec<Action> {
+    let step_ms = params.step_ms;
+    let window_ms = params.window_ms;
 
-    if !is_binary_script(actions) {
-        log::warn!(
-            "actions_to_intensity_curve: input is not a binary (0/100) script — \
-             found non-binary positions. Returning empty intensity curve."
-        );
+    if actions.len() < 2 {
         return Vec::new();
     }
 
     let mut sorted = actions.to_vec();
     sorted.sort_by_key(|a| a.at);
-    merge_same_position_runs(&mut sorted, 200);
+    merge_same_position_runs(&mut sorted, params.merge_gap_ms);
+    let depths = stroke_depths(&sorted);
 
     let end_time = sorted.last().unwrap().at;
     let start_time = sorted.first().unwrap().at;
 
-    const MAX_RISE_PER_SEC: f64 = 40.0;
-    const SMOOTHING: f64 = 0.6;
-    let max_rise_per_step = MAX_RISE_PER_SEC / 1000.0 * step_ms as f64;
+    let max_rise_per_step = params.max_rise_per_sec / 1000.0 * step_ms as f64;
 
     let mut output = Vec::new();
     if start_time > 0 {
@@ -339,7 +538,7 @@ pub fn actions_to_intensity_curve(actions: &[Action], cal_points: &[(f64, f64)])
         let w_start = t.saturating_sub(window_ms);
         let w_end = min(end_time, t + window_ms);
 
-        let mut intensity = window_intensity(&sorted, w_start, w_end, cal_points);
+        let mut intensity = window_intensity(&sorted, &depths, w_start, w_end, cal_points);
 
         // Rate-limit rises
         if intensity > prev_intensity + max_rise_per_step {
@@ -347,7 +546,7 @@ pub fn actions_to_intensity_curve(actions: &[Action], cal_points: &[(f64, f64)])
         }
 
         // Exponential smoothing (only prevents sharp drops)
-        let smoothed = prev_smooth + SMOOTHING * (intensity - prev_smooth);
+        let smoothed = prev_smooth + params.smoothing * (intensity - prev_smooth);
         let final_val = intensity.max(smoothed);
 
         let snapped_time = ((t as f64 / step_ms as f64).round() as u64) * step_ms;
@@ -364,14 +563,6 @@ pub fn actions_to_intensity_curve(actions: &[Action], cal_points: &[(f64, f64)])
     output
 }
 
-/// Only funscripts that contain pos values of either 0 or 100 are allowed.
-fn is_binary_script(actions: &[Action]) -> bool {
-    actions.iter().all(|a| {
-        let p = a.pos.round() as i64;
-        p == 0 || p == 100
-    })
-}
-
 pub fn double_beat_actions(actions: &[Action]) -> Vec<Action> {
     if actions.len() < 2 {
         return actions.to_vec();
@@ -431,13 +622,13 @@ pub fn half_beat_actions(actions: &[Action]) -> Vec<Action> {
     }
 
     let end_time = actions.last().unwrap().at;
-    if let Some(last) = result.last().cloned() {
-        if last.at < end_time {
-            result.push(Action {
-                at: end_time,
-                pos: last.pos,
-            });
-        }
+    if let Some(last) = result.last().cloned()
+        && last.at < end_time
+    {
+        result.push(Action {
+            at: end_time,
+            pos: last.pos,
+        });
     }
 
     merge_same_position_runs(&mut result, 2);
@@ -466,13 +657,13 @@ pub fn quarter_beat_actions(actions: &[Action]) -> Vec<Action> {
     }
 
     let end_time = actions.last().unwrap().at;
-    if let Some(last) = result.last().cloned() {
-        if last.at < end_time {
-            result.push(Action {
-                at: end_time,
-                pos: last.pos,
-            });
-        }
+    if let Some(last) = result.last().cloned()
+        && last.at < end_time
+    {
+        result.push(Action {
+            at: end_time,
+            pos: last.pos,
+        });
     }
 
     merge_same_position_runs(&mut result, 2);
@@ -485,11 +676,12 @@ pub fn intensity_to_bpm(intensity: f64) -> f64 {
     if val <= 0.0 {
         return 0.0;
     }
+    let table = BPM_TABLE.read().unwrap_or_else(|e| e.into_inner());
     if val >= 100.0 {
-        return 270.0;
+        return table.last().map(|p| p.0).unwrap_or(0.0);
     }
 
-    for pair in BPM_TO_INTENSITY.windows(2) {
+    for pair in table.windows(2) {
         let (b0, i0) = pair[0];
         let (b1, i1) = pair[1];
         if val >= i0 && val <= i1 {
@@ -525,7 +717,7 @@ pub fn get_calibrated_intensity(raw_intensity: f64, cal_points: &[(f64, f64)]) -
     let last_idx = cal_points.len() - 1;
     if bpm >= cal_points[last_idx].0 {
         let (b0, i0) = cal_points[last_idx];
-        let b1 = 270.0;
+        let b1 = max_table_bpm();
         let i1 = 100.0;
         if bpm >= b1 {
             return 100.0;
@@ -549,12 +741,12 @@ pub fn get_calibrated_intensity(raw_intensity: f64, cal_points: &[(f64, f64)]) -
     raw_intensity
 }
 
-pub fn calculate_volatility(actions: &[Action]) -> f64 {
+pub fn calculate_volatility(actions: &[Action], params: &IntensityParams) -> f64 {
     if actions.len() < 2 {
         return 0.0;
     }
 
-    let curve_actions = actions_to_intensity_curve(actions, &[]);
+    let curve_actions = actions_to_intensity_curve(actions, &[], params);
     if curve_actions.is_empty() {
         return 0.0;
     }
@@ -678,8 +870,7 @@ fn compute_sections(curve: &[(f64, f64)]) -> Vec<Section> {
         }
 
         let mut new_sections = vec![sections[0].clone()];
-        for j in 1..sections.len() {
-            let sec = &sections[j];
+        for sec in sections.iter().skip(1) {
             let prev = new_sections.last_mut().unwrap();
 
             if sec.bucket == prev.bucket || sec.duration < min_duration || prev.duration < min_duration {
@@ -705,3 +896,378 @@ fn compute_sections(curve: &[(f64, f64)]) -> Vec<Section> {
 
     sections
 }
+
+/// Gap between two actions that is reported as suspicious.
+pub const LINT_LONG_GAP_MS: u64 = 30_000;
+/// How far the last action may lie past the end of the video.
+pub const LINT_VIDEO_OVERRUN_MS: u64 = 5_000;
+/// Diagnostics listed per rule before the rest are summarised.
+const MAX_DIAGNOSTICS_PER_RULE: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintDiagnostic {
+    /// Stable rule id, e.g. `unsorted_actions`.
+    pub rule: &'static str,
+    pub severity: LintSeverity,
+    /// Script time range the problem covers; missing for whole-file problems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_ms: Option<u64>,
+    pub message: String,
+}
+
+impl LintDiagnostic {
+    fn new(rule: &'static str, severity: LintSeverity, range: Option<(u64, u64)>, message: String) -> Self {
+        Self {
+            rule,
+            severity,
+            start_ms: range.map(|r| r.0),
+            end_ms: range.map(|r| r.1),
+            message,
+        }
+    }
+}
+
+/// Check raw funscript JSON. Positions are checked against the script's own `range`, so
+/// this runs on the file as written rather than on `parse_funscript` output.
+/// `video_duration_ms` enables the check against the end of the video.
+pub fn lint_funscript(content: &str, video_duration_ms: Option<u64>) -> Vec<LintDiagnostic> {
+    use LintSeverity::{Error, Warning};
+
+    let data: FunscriptData = match serde_json::from_str(content) {
+        Ok(data) => data,
+        Err(e) => return vec![LintDiagnostic::new("invalid_json", Error, None, e.to_string())],
+    };
+    let actions = &data.actions;
+    let mut diagnostics = Vec::new();
+
+    if actions.len() < 2 {
+        diagnostics.push(LintDiagnostic::new(
+            "too_few_actions",
+            Error,
+            None,
+            format!("{} actions; at least 2 are needed to generate intensity", actions.len()),
+        ));
+    }
+
+    let max_pos = if data.range == 0 || data.range > 100 {
+        diagnostics.push(LintDiagnostic::new(
+            "invalid_range",
+            Warning,
+            None,
+            format!("range {} is outside 1-100; reading positions as 0-100", data.range),
+        ));
+        100.0
+    } else {
+        data.range as f64
+    };
+
+    for action in actions {
+        if !action.pos.is_finite() || action.pos < 0.0 || action.pos > max_pos {
+            diagnostics.push(LintDiagnostic::new(
+                "position_out_of_range",
+                Error,
+                Some((action.at, action.at)),
+                format!("position {} is outside 0-{max_pos}", action.pos),
+            ));
+        }
+    }
+
+    for pair in actions.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        let range = Some((a.at.min(b.at), a.at.max(b.at)));
+        if b.at < a.at {
+            diagnostics.push(LintDiagnostic::new(
+                "unsorted_actions",
+                Error,
+                range,
+                format!("action at {} ms follows one at {} ms", b.at, a.at),
+            ));
+        } else if b.at == a.at {
+            diagnostics.push(LintDiagnostic::new(
+                "duplicate_timestamp",
+                Warning,
+                range,
+                format!("two actions at {} ms", a.at),
+            ));
+        } else if b.at - a.at > LINT_LONG_GAP_MS {
+            diagnostics.push(LintDiagnostic::new(
+                "long_gap",
+                Warning,
+                range,
+                format!("no actions for {:.1} s", (b.at - a.at) as f64 / 1000.0),
+            ));
+        }
+    }
+
+    if let Some(video_ms) = video_duration_ms
+        && let Some(last_at) = actions.iter().map(|a| a.at).max()
+        && last_at > video_ms + LINT_VIDEO_OVERRUN_MS
+    {
+        diagnostics.push(LintDiagnostic::new(
+            "past_video_end",
+            Warning,
+            Some((video_ms, last_at)),
+            format!(
+                "last action is {:.1} s past the end of the video",
+                (last_at - video_ms) as f64 / 1000.0
+            ),
+        ));
+    }
+
+    cap_per_rule(diagnostics)
+}
+
+/// Keep the first `MAX_DIAGNOSTICS_PER_RULE` diagnostics of each rule and summarise the rest.
+fn cap_per_rule(diagnostics: Vec<LintDiagnostic>) -> Vec<LintDiagnostic> {
+    let mut kept: Vec<LintDiagnostic> = Vec::new();
+    let mut dropped: Vec<(&'static str, LintSeverity, usize)> = Vec::new();
+
+    for diagnostic in diagnostics {
+        if kept.iter().filter(|d| d.rule == diagnostic.rule).count() < MAX_DIAGNOSTICS_PER_RULE {
+            kept.push(diagnostic);
+        } else if let Some(entry) = dropped.iter_mut().find(|(rule, _, _)| *rule == diagnostic.rule) {
+            entry.2 += 1;
+        } else {
+            dropped.push((diagnostic.rule, diagnostic.severity, 1));
+        }
+    }
+
+    for (rule, severity, count) in dropped {
+        kept.push(LintDiagnostic::new(rule, severity, None, format!("{count} more like this")));
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strokes alternating between `low` and `high` every `interval_ms`, starting at `low`.
+    fn strokes(low: f64, high: f64, interval_ms: u64, count: u64) -> Vec<Action> {
+        (0..count)
+            .map(|i| Action {
+                at: i * interval_ms,
+                pos: if i % 2 == 0 { low } else { high },
+            })
+            .collect()
+    }
+
+    fn bpm(actions: &[Action], win_start: u64, win_end: u64) -> f64 {
+        window_bpm(actions, &stroke_depths(actions), win_start, win_end)
+    }
+
+    #[test]
+    fn binary_script_matches_total_change_formula() {
+        let actions = strokes(0.0, 100.0, 250, 41);
+        let (win_start, win_end) = (1000, 5000);
+
+        // The formula used before stroke depth was taken into account
+        let total_change: f64 = actions
+            .windows(2)
+            .filter(|pair| pair[0].at >= win_start && pair[1].at <= win_end)
+            .map(|pair| (pair[1].pos - pair[0].pos).abs())
+            .sum();
+        let duration_sec = (win_end - win_start) as f64 / 1000.0;
+        let expected = (total_change / 200.0) * (60.0 / duration_sec);
+
+        assert!((bpm(&actions, win_start, win_end) - expected).abs() < 1e-9);
+        assert!((expected - 120.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn half_depth_strokes_count_by_depth_weight() {
+        let full = bpm(&strokes(0.0, 100.0, 250, 41), 1000, 5000);
+        let half = bpm(&strokes(25.0, 75.0, 250, 41), 1000, 5000);
+
+        assert!((half / full - 0.5f64.powf(STROKE_DEPTH_WEIGHT)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn jitter_below_min_depth_is_ignored() {
+        let jitter = strokes(50.0, 50.0 + MIN_STROKE_DEPTH - 1.0, 100, 51);
+        assert_eq!(bpm(&jitter, 1000, 4000), 0.0);
+
+        // Jitter during a hold between strokes adds nothing either
+        let mut actions = strokes(0.0, 100.0, 250, 9);
+        let hold_start = actions.last().unwrap().at;
+        actions.extend(strokes(0.0, 2.0, 100, 21).into_iter().skip(1).map(|a| Action {
+            at: hold_start + a.at,
+            pos: a.pos,
+        }));
+        assert_eq!(bpm(&actions, hold_start, hold_start + 2000), 0.0);
+    }
+
+    fn positions(content: &str) -> Vec<f64> {
+        parse_funscript(content).unwrap().actions.iter().map(|a| a.pos).collect()
+    }
+
+    #[test]
+    fn inverted_script_is_flipped() {
+        let content = r#"{"inverted": true, "actions": [
+            {"at": 0, "pos": 0}, {"at": 500, "pos": 100}, {"at": 1000, "pos": 30}
+        ]}"#;
+        assert_eq!(positions(content), vec![100.0, 0.0, 70.0]);
+
+        let data = parse_funscript(content).unwrap();
+        assert!(!data.inverted);
+        assert_eq!(data.range, 100);
+    }
+
+    #[test]
+    fn range_is_scaled_to_full_travel() {
+        let content = r#"{"range": 90, "actions": [
+            {"at": 0, "pos": 0}, {"at": 500, "pos": 90}, {"at": 1000, "pos": 45}, {"at": 1500, "pos": 95}
+        ]}"#;
+        // Positions above the range are clamped to the top
+        assert_eq!(positions(content), vec![0.0, 100.0, 50.0, 100.0]);
+    }
+
+    #[test]
+    fn inverted_range_script_is_scaled_then_flipped() {
+        let content = r#"{"inverted": true, "range": 50, "actions": [
+            {"at": 0, "pos": 0}, {"at": 500, "pos": 50}, {"at": 1000, "pos": 10}
+        ]}"#;
+        assert_eq!(positions(content), vec![100.0, 0.0, 80.0]);
+
+        let data = parse_funscript(content).unwrap();
+        let header = FunscriptData {
+            inverted: true,
+            range: 50,
+            ..data
+        };
+        let raw: Vec<f64> = header.actions.iter().map(|a| header.denormalized_position(a.pos)).collect();
+        assert_eq!(raw, vec![0.0, 50.0, 10.0]);
+    }
+
+    /// Lint a script with the given actions as (at, pos) and extra top-level fields.
+    fn lint(actions: &[(u64, f64)], extra: &str, video_duration_ms: Option<u64>) -> Vec<LintDiagnostic> {
+        let actions: Vec<String> = actions
+            .iter()
+            .map(|(at, pos)| format!(r#"{{"at": {at}, "pos": {pos}}}"#))
+            .collect();
+        let content = format!(r#"{{"actions": [{}]{extra}}}"#, actions.join(", "));
+        lint_funscript(&content, video_duration_ms)
+    }
+
+    /// The only diagnostic of `rule`, asserting that no other rule fired.
+    fn single(diagnostics: Vec<LintDiagnostic>, rule: &str) -> LintDiagnostic {
+        let rules: Vec<_> = diagnostics.iter().map(|d| d.rule).collect();
+        assert_eq!(rules, [rule]);
+        diagnostics.into_iter().next().unwrap()
+    }
+
+    fn range(diagnostic: &LintDiagnostic) -> (Option<u64>, Option<u64>) {
+        (diagnostic.start_ms, diagnostic.end_ms)
+    }
+
+    const VALID: &[(u64, f64)] = &[(0, 0.0), (500, 100.0), (1000, 0.0)];
+
+    #[test]
+    fn lint_accepts_a_clean_script() {
+        assert!(lint(VALID, "", Some(1000)).is_empty());
+    }
+
+    #[test]
+    fn lint_reports_invalid_json() {
+        let diagnostic = single(lint_funscript(r#"{"actions": [{"at": 0}"#, None), "invalid_json");
+        assert_eq!(diagnostic.severity, LintSeverity::Error);
+        assert_eq!(range(&diagnostic), (None, None));
+    }
+
+    #[test]
+    fn lint_reports_too_few_actions() {
+        let diagnostic = single(lint(&[(0, 50.0)], "", None), "too_few_actions");
+        assert_eq!(diagnostic.severity, LintSeverity::Error);
+        assert_eq!(diagnostic.message, "1 actions; at least 2 are needed to generate intensity");
+    }
+
+    #[test]
+    fn lint_reports_invalid_range() {
+        let diagnostic = single(lint(VALID, r#", "range": 0"#, None), "invalid_range");
+        assert_eq!(diagnostic.severity, LintSeverity::Warning);
+
+        // Positions are then read as 0-100
+        let rules: Vec<_> = lint(&[(0, 0.0), (500, 101.0)], r#", "range": 150"#, None)
+            .into_iter()
+            .map(|d| d.rule)
+            .collect();
+        assert_eq!(rules, ["invalid_range", "position_out_of_range"]);
+    }
+
+    #[test]
+    fn lint_checks_positions_against_the_script_range() {
+        let actions = [(0, 0.0), (500, 95.0), (1000, 0.0)];
+        assert!(lint(&actions, "", None).is_empty());
+
+        let diagnostic = single(lint(&actions, r#", "range": 90"#, None), "position_out_of_range");
+        assert_eq!(diagnostic.severity, LintSeverity::Error);
+        assert_eq!(range(&diagnostic), (Some(500), Some(500)));
+        assert_eq!(diagnostic.message, "position 95 is outside 0-90");
+
+        let negative = single(lint(&[(0, -1.0), (500, 50.0)], "", None), "position_out_of_range");
+        assert_eq!(range(&negative), (Some(0), Some(0)));
+    }
+
+    #[test]
+    fn lint_reports_unsorted_actions() {
+        let diagnostic = single(lint(&[(0, 0.0), (800, 100.0), (500, 0.0)], "", None), "unsorted_actions");
+        assert_eq!(diagnostic.severity, LintSeverity::Error);
+        assert_eq!(range(&diagnostic), (Some(500), Some(800)));
+    }
+
+    #[test]
+    fn lint_reports_duplicate_timestamps() {
+        let diagnostic = single(lint(&[(0, 0.0), (500, 100.0), (500, 0.0)], "", None), "duplicate_timestamp");
+        assert_eq!(diagnostic.severity, LintSeverity::Warning);
+        assert_eq!(range(&diagnostic), (Some(500), Some(500)));
+    }
+
+    #[test]
+    fn lint_reports_long_gaps() {
+        let end = 500 + LINT_LONG_GAP_MS;
+        assert!(lint(&[(0, 0.0), (500, 100.0), (end, 0.0)], "", None).is_empty());
+
+        let diagnostic = single(lint(&[(0, 0.0), (500, 100.0), (end + 1, 0.0)], "", None), "long_gap");
+        assert_eq!(diagnostic.severity, LintSeverity::Warning);
+        assert_eq!(range(&diagnostic), (Some(500), Some(end + 1)));
+    }
+
+    #[test]
+    fn lint_reports_actions_past_the_video_end() {
+        let actions = [(0, 0.0), (5000, 100.0), (10_000, 0.0)];
+        let video_ms = 10_000 - LINT_VIDEO_OVERRUN_MS;
+        assert!(lint(&actions, "", Some(video_ms)).is_empty());
+        assert!(lint(&actions, "", None).is_empty());
+
+        let diagnostic = single(lint(&actions, "", Some(video_ms - 1)), "past_video_end");
+        assert_eq!(diagnostic.severity, LintSeverity::Warning);
+        assert_eq!(range(&diagnostic), (Some(video_ms - 1), Some(10_000)));
+    }
+
+    #[test]
+    fn lint_summarises_diagnostics_beyond_the_cap() {
+        let extra = 3;
+        let actions: Vec<(u64, f64)> = (0..(MAX_DIAGNOSTICS_PER_RULE + extra) as u64)
+            .map(|i| (i * 100, 120.0))
+            .collect();
+        let diagnostics = lint(&actions, "", None);
+
+        assert_eq!(diagnostics.len(), MAX_DIAGNOSTICS_PER_RULE + 1);
+        assert!(diagnostics.iter().all(|d| d.rule == "position_out_of_range"));
+        assert!(diagnostics[..MAX_DIAGNOSTICS_PER_RULE].iter().all(|d| d.start_ms.is_some()));
+
+        let summary = diagnostics.last().unwrap();
+        assert_eq!(summary.message, format!("{extra} more like this"));
+        assert_eq!(summary.severity, LintSeverity::Error);
+        assert_eq!(range(summary), (None, None));
+    }
+}
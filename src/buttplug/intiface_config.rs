@@ -0,0 +1,191 @@
+// src/buttplug/intiface_config.rs
+
+//! Intiface connection and control-loop configuration
+//!
+//! Defaults can be overridden through environment variables (usually set in `.env`):
+//! - DEVICE_BACKEND: `intiface` (default) or `virtual` for a simulated device
+//! - INTIFACE_URL: WebSocket endpoint of the Intiface server
+//! - INTIFACE_CONTROL_INTERVAL_MS: delay between two device command ticks
+//! - INTIFACE_KEEPALIVE_MS: resend unchanged actuator values after this long (0 = only on change)
+//! - INTIFACE_SCAN_INTERVAL_SECS: delay between two checks for missing devices
+//! - INTIFACE_RECONNECT_DELAY_SECS: initial reconnect delay after a lost connection
+//! - INTIFACE_MAX_RECONNECT_DELAY_SECS: upper bound of the reconnect backoff
+//! - INTIFACE_WATCHDOG_TIMEOUT_MS: client silence after which devices are stopped (0 disables)
+//! - INTIFACE_WATCHDOG_FADE_MS: ramp-down duration once the watchdog trips
+//! - INTIFACE_TELEMETRY_INTERVAL_SECS: delay between two battery/RSSI reads (0 disables)
+//! - INTIFACE_LOW_BATTERY_PERCENT: battery level below which a warning is pushed
+//! - INTIFACE_LOW_BATTERY_PAUSE: also stop playback when a device runs low (true/false)
+//!
+//! The device manager reads the active configuration on every loop iteration, so
+//! values changed at runtime through the admin API apply without a restart.
+
+use crate::buttplug::output::OutputBackend;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{env, str::FromStr};
+
+const DEFAULT_SERVER_URL: &str = "ws://127.0.0.1:12345/buttplug";
+const DEFAULT_CONTROL_INTERVAL_MS: u64 = 100;
+const DEFAULT_KEEPALIVE_MS: u64 = 1000;
+const DEFAULT_SCAN_INTERVAL_SECS: u64 = 5;
+const DEFAULT_RECONNECT_DELAY_SECS: u64 = 5;
+const DEFAULT_MAX_RECONNECT_DELAY_SECS: u64 = 60;
+const DEFAULT_WATCHDOG_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_WATCHDOG_FADE_MS: u64 = 2000;
+const DEFAULT_TELEMETRY_INTERVAL_SECS: u64 = 30;
+const DEFAULT_LOW_BATTERY_PERCENT: u64 = 15;
+
+const MIN_CONTROL_INTERVAL_MS: u64 = 20;
+const MAX_CONTROL_INTERVAL_MS: u64 = 5000;
+const MAX_WATCHDOG_FADE_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntifaceConfig {
+    pub backend: OutputBackend,
+    pub server_url: String,
+    pub control_interval_ms: u64,
+    pub keepalive_ms: u64,
+    pub scan_interval_secs: u64,
+    pub reconnect_delay_secs: u64,
+    pub max_reconnect_delay_secs: u64,
+    pub watchdog_timeout_ms: u64,
+    pub watchdog_fade_ms: u64,
+    pub telemetry_interval_secs: u64,
+    pub low_battery_percent: u64,
+    pub low_battery_pause: bool,
+}
+
+impl Default for IntifaceConfig {
+    fn default() -> Self {
+        Self {
+            backend: OutputBackend::default(),
+            server_url: DEFAULT_SERVER_URL.to_string(),
+            control_interval_ms: DEFAULT_CONTROL_INTERVAL_MS,
+            keepalive_ms: DEFAULT_KEEPALIVE_MS,
+            scan_interval_secs: DEFAULT_SCAN_INTERVAL_SECS,
+            reconnect_delay_secs: DEFAULT_RECONNECT_DELAY_SECS,
+            max_reconnect_delay_secs: DEFAULT_MAX_RECONNECT_DELAY_SECS,
+            watchdog_timeout_ms: DEFAULT_WATCHDOG_TIMEOUT_MS,
+            watchdog_fade_ms: DEFAULT_WATCHDOG_FADE_MS,
+            telemetry_interval_secs: DEFAULT_TELEMETRY_INTERVAL_SECS,
+            low_battery_percent: DEFAULT_LOW_BATTERY_PERCENT,
+            low_battery_pause: false,
+        }
+    }
+}
+
+/// Partial update for the admin API; missing fields keep their current value.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IntifaceConfigUpdate {
+    pub backend: Option<OutputBackend>,
+    pub server_url: Option<String>,
+    pub control_interval_ms: Option<u64>,
+    pub keepalive_ms: Option<u64>,
+    pub scan_interval_secs: Option<u64>,
+    pub reconnect_delay_secs: Option<u64>,
+    pub max_reconnect_delay_secs: Option<u64>,
+    pub watchdog_timeout_ms: Option<u64>,
+    pub watchdog_fade_ms: Option<u64>,
+    pub telemetry_interval_secs: Option<u64>,
+    pub low_battery_percent: Option<u64>,
+    pub low_battery_pause: Option<bool>,
+}
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match env::var(key) {
+        Ok(raw) => raw.trim().parse().unwrap_or_else(|_| {
+            warn!("Ignoring invalid {key} value {raw:?}, using default");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+impl IntifaceConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let config = Self {
+            backend: env_or("DEVICE_BACKEND", defaults.backend),
+            server_url: env_or("INTIFACE_URL", defaults.server_url.clone()),
+            control_interval_ms: env_or("INTIFACE_CONTROL_INTERVAL_MS", defaults.control_interval_ms),
+            keepalive_ms: env_or("INTIFACE_KEEPALIVE_MS", defaults.keepalive_ms),
+            scan_interval_secs: env_or("INTIFACE_SCAN_INTERVAL_SECS", defaults.scan_interval_secs),
+            reconnect_delay_secs: env_or("INTIFACE_RECONNECT_DELAY_SECS", defaults.reconnect_delay_secs),
+            max_reconnect_delay_secs: env_or(
+                "INTIFACE_MAX_RECONNECT_DELAY_SECS",
+                defaults.max_reconnect_delay_secs,
+            ),
+            watchdog_timeout_ms: env_or("INTIFACE_WATCHDOG_TIMEOUT_MS", defaults.watchdog_timeout_ms),
+            watchdog_fade_ms: env_or("INTIFACE_WATCHDOG_FADE_MS", defaults.watchdog_fade_ms),
+            telemetry_interval_secs: env_or(
+                "INTIFACE_TELEMETRY_INTERVAL_SECS",
+                defaults.telemetry_interval_secs,
+            ),
+            low_battery_percent: env_or("INTIFACE_LOW_BATTERY_PERCENT", defaults.low_battery_percent),
+            low_battery_pause: env_or("INTIFACE_LOW_BATTERY_PAUSE", defaults.low_battery_pause),
+        };
+
+        match config.validate() {
+            Ok(()) => config,
+            Err(e) => {
+                warn!("Invalid Intiface configuration ({e}), using defaults");
+                defaults
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.server_url.starts_with("ws://") || self.server_url.starts_with("wss://")) {
+            return Err("server_url must start with ws:// or wss://".to_string());
+        }
+        if !(MIN_CONTROL_INTERVAL_MS..=MAX_CONTROL_INTERVAL_MS).contains(&self.control_interval_ms) {
+            return Err(format!(
+                "control_interval_ms must be between {MIN_CONTROL_INTERVAL_MS} and {MAX_CONTROL_INTERVAL_MS}"
+            ));
+        }
+        if self.keepalive_ms != 0 && self.keepalive_ms < self.control_interval_ms {
+            return Err("keepalive_ms must be 0 or at least control_interval_ms".to_string());
+        }
+        if self.scan_interval_secs == 0 {
+            return Err("scan_interval_secs must be at least 1".to_string());
+        }
+        if self.reconnect_delay_secs == 0 {
+            return Err("reconnect_delay_secs must be at least 1".to_string());
+        }
+        if self.max_reconnect_delay_secs < self.reconnect_delay_secs {
+            return Err("max_reconnect_delay_secs must not be below reconnect_delay_secs".to_string());
+        }
+        if self.watchdog_fade_ms > MAX_WATCHDOG_FADE_MS {
+            return Err(format!("watchdog_fade_ms must not exceed {MAX_WATCHDOG_FADE_MS}"));
+        }
+        if self.low_battery_percent > 100 {
+            return Err("low_battery_percent must not exceed 100".to_string());
+        }
+        Ok(())
+    }
+
+    /// Apply a partial update, returning the validated result without modifying `self`.
+    pub fn merged(&self, update: IntifaceConfigUpdate) -> Result<Self, String> {
+        let merged = Self {
+            backend: update.backend.unwrap_or(self.backend),
+            server_url: update
+                .server_url
+                .map(|u| u.trim().to_string())
+                .unwrap_or_else(|| self.server_url.clone()),
+            control_interval_ms: update.control_interval_ms.unwrap_or(self.control_interval_ms),
+            keepalive_ms: update.keepalive_ms.unwrap_or(self.keepalive_ms),
+            scan_interval_secs: update.scan_interval_secs.unwrap_or(self.scan_interval_secs),
+            reconnect_delay_secs: update.reconnect_delay_secs.unwrap_or(self.reconnect_delay_secs),
+            max_reconnect_delay_secs: update
+                .max_reconnect_delay_secs
+                .unwrap_or(self.max_reconnect_delay_secs),
+            watchdog_timeout_ms: update.watchdog_timeout_ms.unwrap_or(self.watchdog_timeout_ms),
+            watchdog_fade_ms: update.watchdog_fade_ms.unwrap_or(self.watchdog_fade_ms),
+            telemetry_interval_secs: update.telemetry_interval_secs.unwrap_or(self.telemetry_interval_secs),
+            low_battery_percent: update.low_battery_percent.unwrap_or(self.low_battery_percent),
+            low_battery_pause: update.low_battery_pause.unwrap_or(self.low_battery_pause),
+        };
+        merged.validate()?;
+        Ok(merged)
+    }
+}
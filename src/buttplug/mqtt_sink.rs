@@ -0,0 +1,309 @@
+// src/buttplug/mqtt_sink.rs
+
+//! MQTT output
+//!
+//! A minimal MQTT 3.1.1 client that only publishes (QoS 0) to `MQTT_BROKER` (host or
+//! host:port, default port 1883). Topics under `MQTT_TOPIC_PREFIX` (default `hismith`):
+//! - `<prefix>/intensity` (retained, e.g. `0.42`) whenever the intensity changes by at least 1%
+//! - `<prefix>/playing` and `<prefix>/stopped` (retained, `true`/`false`) when they change
+//! - `<prefix>/beat` (script time in ms) on every stroke hit
+//!
+//! The connection is re-established after a few seconds when it drops; frames are
+//! discarded while disconnected.
+
+use crate::buttplug::output_sink::{OutputSink, SINK_QUEUE_CAPACITY, SinkFrame};
+use log::{info, warn};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::mpsc,
+    time::{self, MissedTickBehavior},
+};
+
+const DEFAULT_PORT: u16 = 1883;
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PINGREQ: u8 = 0xC0;
+
+#[derive(Debug, Clone)]
+pub struct MqttOptions {
+    pub broker: String,
+    pub client_id: String,
+    pub topic_prefix: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+pub struct MqttSink {
+    name: String,
+    frames: mpsc::Sender<SinkFrame>,
+}
+
+impl MqttSink {
+    /// Start the publishing task. Connection errors are logged and retried in the background.
+    pub fn start(mut options: MqttOptions) -> Self {
+        let broker = options.broker.trim_start_matches("mqtt://").trim_end_matches('/');
+        let has_port = broker.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+        options.broker = if has_port {
+            broker.to_string()
+        } else {
+            format!("{broker}:{DEFAULT_PORT}")
+        };
+        options.topic_prefix = options.topic_prefix.trim_end_matches('/').to_string();
+
+        let (tx, rx) = mpsc::channel(SINK_QUEUE_CAPACITY);
+        let name = format!("MQTT {}", options.broker);
+        tokio::spawn(run(options, rx));
+        Self { name, frames: tx }
+    }
+}
+
+impl OutputSink for MqttSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish(&self, frame: &SinkFrame) {
+        let _ = self.frames.try_send(frame.clone());
+    }
+}
+
+fn push_remaining_length(buf: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        buf.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+fn push_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(body.len() + 5);
+    buf.push(header);
+    push_remaining_length(&mut buf, body.len());
+    buf.extend_from_slice(body);
+    buf
+}
+
+fn connect_packet(options: &MqttOptions) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, "MQTT");
+    body.push(4); // protocol level 3.1.1
+
+    // Clean session; a password is only allowed together with a user name
+    let username = options.username.as_deref();
+    let password = username.and(options.password.as_deref());
+    let mut flags = 0x02;
+    if username.is_some() {
+        flags |= 0x80;
+    }
+    if password.is_some() {
+        flags |= 0x40;
+    }
+    body.push(flags);
+    body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+
+    push_str(&mut body, &options.client_id);
+    if let Some(username) = username {
+        push_str(&mut body, username);
+    }
+    if let Some(password) = password {
+        push_str(&mut body, password);
+    }
+    packet(CONNECT, &body)
+}
+
+fn publish_packet(topic: &str, payload: &str, retain: bool) -> Vec<u8> {
+    let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+    push_str(&mut body, topic);
+    body.extend_from_slice(payload.as_bytes());
+    packet(PUBLISH | retain as u8, &body)
+}
+
+async fn connect(options: &MqttOptions) -> Result<TcpStream, String> {
+    let handshake = async {
+        let mut stream = TcpStream::connect(&options.broker).await.map_err(|e| e.to_string())?;
+        stream.write_all(&connect_packet(options)).await.map_err(|e| e.to_string())?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack).await.map_err(|e| e.to_string())?;
+        if connack[0] != CONNACK {
+            return Err(format!("unexpected packet 0x{:02x} instead of CONNACK", connack[0]));
+        }
+        if connack[3] != 0 {
+            return Err(format!("connection refused (return code {})", connack[3]));
+        }
+        Ok(stream)
+    };
+    time::timeout(CONNECT_TIMEOUT, handshake)
+        .await
+        .map_err(|_| "timed out".to_string())?
+}
+
+/// Publish frames until the connection drops. Returns `false` once the sink is gone.
+async fn publish_frames(options: &MqttOptions, stream: TcpStream, frames: &mut mpsc::Receiver<SinkFrame>) -> bool {
+    let (mut reader, mut writer) = stream.into_split();
+    let prefix = &options.topic_prefix;
+    let mut keep_alive = time::interval(KEEP_ALIVE / 2);
+    keep_alive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut read_buf = [0u8; 64];
+    let mut last: Option<(u32, bool, bool)> = None;
+
+    loop {
+        let packets = tokio::select! {
+            frame = frames.recv() => {
+                let Some(frame) = frame else { return false };
+                let percent = (frame.intensity * 100.0).round() as u32;
+                let mut packets = Vec::new();
+                if last.is_none_or(|(p, _, _)| p != percent) {
+                    packets.push(publish_packet(&format!("{prefix}/intensity"), &format!("{:.2}", frame.intensity), true));
+                }
+                if last.is_none_or(|(_, playing, _)| playing != frame.playing) {
+                    packets.push(publish_packet(&format!("{prefix}/playing"), &frame.playing.to_string(), true));
+                }
+                if last.is_none_or(|(_, _, stopped)| stopped != frame.stopped) {
+                    packets.push(publish_packet(&format!("{prefix}/stopped"), &frame.stopped.to_string(), true));
+                }
+                for beat in &frame.beats {
+                    packets.push(publish_packet(&format!("{prefix}/beat"), &beat.to_string(), false));
+                }
+                last = Some((percent, frame.playing, frame.stopped));
+                packets
+            }
+            _ = keep_alive.tick() => vec![packet(PINGREQ, &[])],
+            // Only PINGRESP is expected back; the read just notices a closed connection
+            read = reader.read(&mut read_buf) => match read {
+                Ok(0) | Err(_) => return true,
+                Ok(_) => Vec::new(),
+            },
+        };
+
+        for packet in packets {
+            if let Err(e) = writer.write_all(&packet).await {
+                warn!("MQTT publish to {} failed: {e}", options.broker);
+                return true;
+            }
+        }
+    }
+}
+
+async fn run(options: MqttOptions, mut frames: mpsc::Receiver<SinkFrame>) {
+    loop {
+        match connect(&options).await {
+            Ok(stream) => {
+                info!("Connected to MQTT broker {}", options.broker);
+                if !publish_frames(&options, stream, &mut frames).await {
+                    return;
+                }
+                warn!("Lost connection to MQTT broker {}", options.broker);
+            }
+            Err(e) => warn!("Could not connect to MQTT broker {}: {e}", options.broker),
+        }
+
+        time::sleep(RECONNECT_DELAY).await;
+        // Drop what queued up while disconnected
+        loop {
+            match frames.try_recv() {
+                Ok(_) => continue,
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn options(username: Option<&str>, password: Option<&str>) -> MqttOptions {
+        MqttOptions {
+            broker: "localhost".to_string(),
+            client_id: "hismith".to_string(),
+            topic_prefix: "test".to_string(),
+            username: username.map(str::to_string),
+            password: password.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn remaining_length_uses_seven_bits_per_byte() {
+        let encoded = |len| {
+            let mut buf = Vec::new();
+            push_remaining_length(&mut buf, len);
+            buf
+        };
+        assert_eq!(encoded(0), [0x00]);
+        assert_eq!(encoded(127), [0x7f]);
+        assert_eq!(encoded(128), [0x80, 0x01]);
+        assert_eq!(encoded(16383), [0xff, 0x7f]);
+        assert_eq!(encoded(16384), [0x80, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn connect_packet_sets_clean_session_and_credentials() {
+        let anonymous: &[u8] = b"\x10\x13\x00\x04MQTT\x04\x02\x00\x3c\x00\x07hismith";
+        assert_eq!(connect_packet(&options(None, None)), anonymous);
+        // A password without a user name is not sent
+        assert_eq!(connect_packet(&options(None, Some("secret"))), anonymous);
+
+        let login: &[u8] = b"\x10\x1f\x00\x04MQTT\x04\xc2\x00\x3c\x00\x07hismith\x00\x02me\x00\x06secret";
+        assert_eq!(connect_packet(&options(Some("me"), Some("secret"))), login);
+    }
+
+    #[test]
+    fn publish_and_ping_packets() {
+        assert_eq!(
+            publish_packet("test/intensity", "0.42", true),
+            b"\x31\x14\x00\x0etest/intensity0.42"
+        );
+        assert_eq!(publish_packet("test/beat", "1000", false), b"\x30\x0f\x00\x09test/beat1000");
+        assert_eq!(packet(PINGREQ, &[]), [0xc0, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn mqtt_sink_connects_and_publishes_retained_intensity() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut options = options(None, None);
+        options.broker = listener.local_addr().unwrap().to_string();
+        let expected_connect = connect_packet(&options);
+        let sink = MqttSink::start(options);
+
+        let broker = async {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut connect = vec![0u8; expected_connect.len()];
+            stream.read_exact(&mut connect).await.unwrap();
+            assert_eq!(connect, expected_connect);
+            stream.write_all(&[CONNACK, 0x02, 0x00, 0x00]).await.unwrap();
+
+            sink.publish(&SinkFrame {
+                intensity: 0.5,
+                ..Default::default()
+            });
+            let expected_publish = publish_packet("test/intensity", "0.50", true);
+            let mut publish = vec![0u8; expected_publish.len()];
+            stream.read_exact(&mut publish).await.unwrap();
+            assert_eq!(publish, expected_publish);
+        };
+        time::timeout(Duration::from_secs(5), broker)
+            .await
+            .expect("MQTT sink did not connect and publish");
+    }
+}
@@ -0,0 +1,174 @@
+// src/buttplug/osc_sink.rs
+
+//! OSC output over UDP
+//!
+//! Sends OSC 1.0 messages to `OSC_TARGET` (host:port) under a configurable address prefix
+//! (default `/hismith`):
+//! - `<prefix>/intensity` (float 0..1) whenever the intensity changes by at least 1%
+//! - `<prefix>/playing` (int 0/1) and `<prefix>/stopped` (int 0/1) when they change
+//! - `<prefix>/beat` (int, script time in ms) on every stroke hit
+
+use crate::buttplug::output_sink::{OutputSink, SINK_QUEUE_CAPACITY, SinkFrame};
+use log::{debug, warn};
+use std::net::{SocketAddr, ToSocketAddrs};
+use tokio::{net::UdpSocket, sync::mpsc};
+
+enum OscArg {
+    Float(f32),
+    Int(i32),
+}
+
+/// Append `s` as an OSC string: NUL terminated and padded to a multiple of four bytes.
+fn push_osc_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+    while !buf.len().is_multiple_of(4) {
+        buf.push(0);
+    }
+}
+
+fn osc_message(address: &str, arg: OscArg) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(address.len() + 12);
+    push_osc_string(&mut buf, address);
+    match arg {
+        OscArg::Float(v) => {
+            push_osc_string(&mut buf, ",f");
+            buf.extend_from_slice(&v.to_be_bytes());
+        }
+        OscArg::Int(v) => {
+            push_osc_string(&mut buf, ",i");
+            buf.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+    buf
+}
+
+pub struct OscSink {
+    name: String,
+    frames: mpsc::Sender<SinkFrame>,
+}
+
+impl OscSink {
+    /// Resolve `target` and start the sending task.
+    pub fn start(target: &str, prefix: &str) -> Result<Self, String> {
+        let target: SocketAddr = target
+            .to_socket_addrs()
+            .map_err(|e| format!("invalid OSC_TARGET {target:?}: {e}"))?
+            .next()
+            .ok_or_else(|| format!("OSC_TARGET {target:?} did not resolve"))?;
+
+        let (tx, rx) = mpsc::channel(SINK_QUEUE_CAPACITY);
+        tokio::spawn(run(target, prefix.trim_end_matches('/').to_string(), rx));
+        Ok(Self {
+            name: format!("OSC {target}"),
+            frames: tx,
+        })
+    }
+}
+
+impl OutputSink for OscSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish(&self, frame: &SinkFrame) {
+        let _ = self.frames.try_send(frame.clone());
+    }
+}
+
+async fn run(target: SocketAddr, prefix: String, mut frames: mpsc::Receiver<SinkFrame>) {
+    let bind_addr = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = match UdpSocket::bind(bind_addr).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("OSC output disabled, could not open a UDP socket: {e}");
+            return;
+        }
+    };
+
+    let mut last: Option<(u32, bool, bool)> = None;
+    while let Some(frame) = frames.recv().await {
+        let percent = (frame.intensity * 100.0).round() as u32;
+        let mut messages = Vec::new();
+        if last.is_none_or(|(p, _, _)| p != percent) {
+            messages.push(osc_message(&format!("{prefix}/intensity"), OscArg::Float(frame.intensity as f32)));
+        }
+        if last.is_none_or(|(_, playing, _)| playing != frame.playing) {
+            messages.push(osc_message(&format!("{prefix}/playing"), OscArg::Int(frame.playing as i32)));
+        }
+        if last.is_none_or(|(_, _, stopped)| stopped != frame.stopped) {
+            messages.push(osc_message(&format!("{prefix}/stopped"), OscArg::Int(frame.stopped as i32)));
+        }
+        for beat in &frame.beats {
+            messages.push(osc_message(&format!("{prefix}/beat"), OscArg::Int(*beat as i32)));
+        }
+        last = Some((percent, frame.playing, frame.stopped));
+
+        for message in messages {
+            if let Err(e) = socket.send_to(&message, target).await {
+                debug!("OSC send to {target} failed: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time;
+
+    #[test]
+    fn osc_strings_are_nul_terminated_and_padded() {
+        let padded = |s: &str| {
+            let mut buf = Vec::new();
+            push_osc_string(&mut buf, s);
+            buf
+        };
+        assert_eq!(padded(""), b"\0\0\0\0");
+        assert_eq!(padded("abc"), b"abc\0");
+        assert_eq!(padded("abcd"), b"abcd\0\0\0\0");
+        assert_eq!(padded("/hismith/beat"), b"/hismith/beat\0\0\0");
+    }
+
+    #[test]
+    fn osc_messages_carry_type_tag_and_big_endian_argument() {
+        assert_eq!(osc_message("/a", OscArg::Float(0.5)), b"/a\0\0,f\0\0\x3f\x00\x00\x00");
+        assert_eq!(
+            osc_message("/hismith/beat", OscArg::Int(1000)),
+            b"/hismith/beat\0\0\0,i\0\0\x00\x00\x03\xe8"
+        );
+    }
+
+    #[tokio::test]
+    async fn osc_sink_sends_frames_over_udp() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let target = receiver.local_addr().unwrap().to_string();
+        let sink = OscSink::start(&target, "/test/").unwrap();
+        sink.publish(&SinkFrame {
+            intensity: 0.5,
+            playing: true,
+            beats: vec![1000],
+            ..Default::default()
+        });
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 64];
+        for _ in 0..4 {
+            let len = time::timeout(Duration::from_secs(5), receiver.recv(&mut buf))
+                .await
+                .expect("no OSC message received")
+                .unwrap();
+            received.push(buf[..len].to_vec());
+        }
+        assert_eq!(
+            received,
+            [
+                osc_message("/test/intensity", OscArg::Float(0.5)),
+                osc_message("/test/playing", OscArg::Int(1)),
+                osc_message("/test/stopped", OscArg::Int(0)),
+                osc_message("/test/beat", OscArg::Int(1000)),
+            ]
+        );
+    }
+}
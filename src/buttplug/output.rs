@@ -0,0 +1,178 @@
+// src/buttplug/output.rs
+
+//! Output device abstraction
+//!
+//! The control loop drives devices through `OutputDevice`, which wraps either a real
+//! device reached through the Intiface server or a simulated `VirtualDevice`. Both expose
+//! the same actuator layout (scalar, linear and rotate families, each indexed from 0) and
+//! the same commands, so the rest of the device manager does not care which one it talks to.
+//! Commands carry the time of the control tick that issued them; the virtual device
+//! records it as the command's timestamp.
+//!
+//! The backend is chosen with `OutputBackend` in the Intiface configuration.
+
+use crate::buttplug::{device_settings::ActuatorKind, virtual_device::VirtualDevice};
+use buttplug::{
+    client::device::{ButtplugClientDevice, LinearCommand, RotateCommand, ScalarCommand},
+    core::message::ActuatorType,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Instant};
+
+/// Where device output goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputBackend {
+    /// Real hardware through the Intiface server.
+    #[default]
+    Intiface,
+    /// A simulated device that records every command, for demos and testing.
+    Virtual,
+}
+
+impl FromStr for OutputBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "intiface" => Ok(OutputBackend::Intiface),
+            "virtual" => Ok(OutputBackend::Virtual),
+            other => Err(format!("unknown output backend {other:?}")),
+        }
+    }
+}
+
+/// Type, resolution and description of one actuator within its command family.
+#[derive(Debug, Clone)]
+pub struct ActuatorSpec {
+    pub actuator_type: ActuatorType,
+    /// Number of distinct non-zero levels the actuator supports.
+    pub step_count: u32,
+    pub description: String,
+}
+
+#[derive(Clone)]
+pub enum OutputDevice {
+    Intiface(Arc<ButtplugClientDevice>),
+    Virtual(Arc<VirtualDevice>),
+}
+
+impl OutputDevice {
+    pub fn index(&self) -> u32 {
+        match self {
+            OutputDevice::Intiface(dev) => dev.index(),
+            OutputDevice::Virtual(dev) => dev.index(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            OutputDevice::Intiface(dev) => dev.name(),
+            OutputDevice::Virtual(dev) => dev.name(),
+        }
+    }
+
+    pub fn is_virtual(&self) -> bool {
+        matches!(self, OutputDevice::Virtual(_))
+    }
+
+    pub fn is_connected(&self) -> bool {
+        match self {
+            OutputDevice::Intiface(dev) => dev.connected(),
+            OutputDevice::Virtual(_) => true,
+        }
+    }
+
+    /// Battery level (0.0 .. 1.0), or `None` when the device has no battery sensor.
+    pub async fn battery_level(&self) -> Result<Option<f64>, String> {
+        match self {
+            OutputDevice::Intiface(dev) if dev.has_battery_level() => {
+                dev.battery_level().await.map(Some).map_err(|e| e.to_string())
+            }
+            OutputDevice::Intiface(_) => Ok(None),
+            OutputDevice::Virtual(dev) => Ok(Some(dev.battery_level())),
+        }
+    }
+
+    /// Signal strength in dBm, or `None` when the device does not report it.
+    pub async fn rssi_level(&self) -> Result<Option<i32>, String> {
+        match self {
+            OutputDevice::Intiface(dev) if dev.has_rssi_level() => {
+                dev.rssi_level().await.map(Some).map_err(|e| e.to_string())
+            }
+            OutputDevice::Intiface(_) | OutputDevice::Virtual(_) => Ok(None),
+        }
+    }
+
+    pub fn actuators(&self, kind: ActuatorKind) -> Vec<ActuatorSpec> {
+        match self {
+            OutputDevice::Intiface(dev) => {
+                let attrs = match kind {
+                    ActuatorKind::Scalar => dev.scalar_attributes(),
+                    ActuatorKind::Linear => dev.linear_attributes(),
+                    ActuatorKind::Rotate => dev.rotate_attributes(),
+                };
+                attrs
+                    .iter()
+                    .map(|attr| ActuatorSpec {
+                        actuator_type: *attr.actuator_type(),
+                        step_count: *attr.step_count(),
+                        description: attr.feature_descriptor().clone(),
+                    })
+                    .collect()
+            }
+            OutputDevice::Virtual(dev) => dev.actuators(kind),
+        }
+    }
+
+    pub async fn scalar(&self, values: HashMap<u32, (f64, ActuatorType)>, at: Instant) -> Result<(), String> {
+        match self {
+            OutputDevice::Intiface(dev) => dev
+                .scalar(&ScalarCommand::ScalarMap(values))
+                .await
+                .map_err(|e| e.to_string()),
+            OutputDevice::Virtual(dev) => {
+                dev.scalar(values, at);
+                Ok(())
+            }
+        }
+    }
+
+    /// Move linear actuators: values are (duration ms, position 0.0 .. 1.0).
+    pub async fn linear(&self, values: HashMap<u32, (u32, f64)>, at: Instant) -> Result<(), String> {
+        match self {
+            OutputDevice::Intiface(dev) => dev
+                .linear(&LinearCommand::LinearMap(values))
+                .await
+                .map_err(|e| e.to_string()),
+            OutputDevice::Virtual(dev) => {
+                dev.linear(values, at);
+                Ok(())
+            }
+        }
+    }
+
+    /// Rotate actuators: values are (speed 0.0 .. 1.0, clockwise).
+    pub async fn rotate(&self, values: HashMap<u32, (f64, bool)>, at: Instant) -> Result<(), String> {
+        match self {
+            OutputDevice::Intiface(dev) => dev
+                .rotate(&RotateCommand::RotateMap(values))
+                .await
+                .map_err(|e| e.to_string()),
+            OutputDevice::Virtual(dev) => {
+                dev.rotate(values, at);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn stop(&self, at: Instant) -> Result<(), String> {
+        match self {
+            OutputDevice::Intiface(dev) => dev.stop().await.map_err(|e| e.to_string()),
+            OutputDevice::Virtual(dev) => {
+                dev.stop(at);
+                Ok(())
+            }
+        }
+    }
+}
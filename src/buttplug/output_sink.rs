@@ -0,0 +1,77 @@
+// src/buttplug/output_sink.rs
+
+//! Additional outputs for the control loop
+//!
+//! Devices are driven through `OutputDevice` (Intiface or the virtual device). Besides
+//! them, the control loop hands a `SinkFrame` with the applied intensity and the stroke
+//! hits of the tick to every configured `OutputSink`, so lights, fans and other tools can
+//! follow the session. `publish` is called from the control loop and must not block:
+//! the bundled sinks queue the frame for a background task that does the network I/O and
+//! drop frames while that task is behind or disconnected.
+//!
+//! Sinks are enabled from the environment: `OSC_TARGET` sends OSC messages over UDP (see
+//! `osc_sink`) and `MQTT_BROKER` publishes to an MQTT broker (see `mqtt_sink`).
+
+use crate::buttplug::{
+    mqtt_sink::{MqttOptions, MqttSink},
+    osc_sink::OscSink,
+};
+use log::{info, warn};
+use serde::Serialize;
+use std::{env, sync::Arc};
+
+/// Frames queued per sink before new ones are dropped.
+pub const SINK_QUEUE_CAPACITY: usize = 32;
+
+/// Output of one control tick as seen by the sinks.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SinkFrame {
+    /// Highest value the scalar and rotate actuators were sent (0.0 .. 1.0), after curves,
+    /// scale, slew and the max limit, as in `AppliedOutput`; zero while stopped.
+    pub intensity: f64,
+    pub position_ms: f64,
+    pub playing: bool,
+    pub stopped: bool,
+    /// Script times (ms) of the stroke hits passed during this tick.
+    pub beats: Vec<u64>,
+}
+
+pub trait OutputSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Hand over the output of a control tick. Must return immediately.
+    fn publish(&self, frame: &SinkFrame);
+}
+
+/// Build the sinks configured in the environment. Must be called inside the runtime.
+pub fn sinks_from_env() -> Vec<Arc<dyn OutputSink>> {
+    let mut sinks: Vec<Arc<dyn OutputSink>> = Vec::new();
+
+    if let Ok(target) = env::var("OSC_TARGET")
+        && !target.trim().is_empty()
+    {
+        let prefix = env::var("OSC_ADDRESS_PREFIX").unwrap_or_else(|_| "/hismith".to_string());
+        match OscSink::start(target.trim(), &prefix) {
+            Ok(sink) => sinks.push(Arc::new(sink)),
+            Err(e) => warn!("OSC output disabled: {e}"),
+        }
+    }
+
+    if let Ok(broker) = env::var("MQTT_BROKER")
+        && !broker.trim().is_empty()
+    {
+        let options = MqttOptions {
+            broker: broker.trim().to_string(),
+            client_id: env::var("MQTT_CLIENT_ID").unwrap_or_else(|_| "hismith-player".to_string()),
+            topic_prefix: env::var("MQTT_TOPIC_PREFIX").unwrap_or_else(|_| "hismith".to_string()),
+            username: env::var("MQTT_USERNAME").ok(),
+            password: env::var("MQTT_PASSWORD").ok(),
+        };
+        sinks.push(Arc::new(MqttSink::start(options)));
+    }
+
+    for sink in &sinks {
+        info!("Publishing output to {}", sink.name());
+    }
+    sinks
+}
@@ -0,0 +1,404 @@
+// src/buttplug/playback.rs
+
+//! Server-side playback engine
+//!
+//! Owns the intensity timeline of the currently loaded funscript and tracks the
+//! playback transport (play/pause/seek) reported by the client. The device control
+//! loop asks the engine for the output values at the current instant, so the script
+//! position is extrapolated on the server and client timing jitter (throttled tabs,
+//! flaky Wi-Fi) no longer turns into device jitter.
+//!
+//! Linear (position) devices are not sampled; they receive the upcoming action of the
+//! raw script together with the time left to reach it, see `LinearTarget`.
+//!
+//! Conventions:
+//! - Script positions are milliseconds (f64 while extrapolating).
+//! - Intensity values are 0.0 .. 100.0, output values are normalized 0.0 .. 1.0.
+
+use crate::buttplug::funscript_utils::{Action, MIN_STROKE_DEPTH, stroke_depths};
+use std::time::{Duration, Instant};
+
+/// Duration of the fade-in after play and the fade-out after pause.
+pub const TRANSITION_DURATION: Duration = Duration::from_millis(1000);
+
+/// Fallback distance to the next beat when the script has no further beats.
+const BEAT_FALLBACK_MS: f64 = 500.0;
+
+const VIBRATE_DEADZONE: f64 = 0.03;
+const VIBRATE_SCALE: f64 = 1.5;
+
+/// Linear fade between two output levels, used for the play/pause transitions.
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    start: Instant,
+    from: f64,
+    to: f64,
+    duration: Duration,
+}
+
+impl Fade {
+    /// Constant level, no transition.
+    fn hold(level: f64, now: Instant) -> Self {
+        Self {
+            start: now,
+            from: level,
+            to: level,
+            duration: Duration::ZERO,
+        }
+    }
+
+    fn level(&self, now: Instant) -> f64 {
+        if self.duration.is_zero() {
+            return self.to;
+        }
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f64();
+        let t = (elapsed / self.duration.as_secs_f64()).clamp(0.0, 1.0);
+        self.from + (self.to - self.from) * t
+    }
+
+    /// Start a new fade from the current level towards `to`.
+    fn towards(&self, to: f64, duration: Duration, now: Instant) -> Self {
+        Self {
+            start: now,
+            from: self.level(now),
+            to,
+            duration,
+        }
+    }
+}
+
+/// Next raw script action a linear actuator should move to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearTarget {
+    /// Index of the action in the loaded script, used to send each move only once.
+    pub action_index: usize,
+    /// Incremented on every load/play/pause/seek so moves are re-sent after a jump.
+    pub epoch: u64,
+    /// Time left until the action is due.
+    pub duration_ms: u32,
+    /// Target position, normalized to 0.0 .. 1.0.
+    pub position: f64,
+}
+
+/// Output values for one control tick, normalized to 0.0 .. 1.0. Each actuator
+/// picks the value matching its assigned role.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlaybackOutput {
+    pub stroke: f64,
+    pub rate_vibration: f64,
+    pub beat_vibration: f64,
+    /// Upcoming move for linear actuators; `None` while paused or in manual mode.
+    pub linear: Option<LinearTarget>,
+    /// Rotation direction for alternating rotation: clockwise on up-strokes.
+    pub clockwise: bool,
+}
+
+pub struct PlaybackEngine {
+    source: Option<String>,
+    actions: Vec<Action>,
+    intensity: Vec<Action>,
+    beats: Vec<u64>,
+    max_intensity: f64,
+    playing: bool,
+    anchor_pos_ms: f64,
+    anchor_at: Instant,
+    fade: Fade,
+    manual: Option<f64>,
+    /// Level applied to the manual override, so a halt can ramp it down as well.
+    manual_fade: Fade,
+    epoch: u64,
+    /// Start of the current soft-start ramp (play, seek or re-arm).
+    ramp_start: Instant,
+}
+
+impl Default for PlaybackEngine {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            source: None,
+            actions: Vec::new(),
+            intensity: Vec::new(),
+            beats: Vec::new(),
+            max_intensity: 0.0,
+            playing: false,
+            anchor_pos_ms: 0.0,
+            anchor_at: now,
+            fade: Fade::hold(0.0, now),
+            manual: None,
+            manual_fade: Fade::hold(1.0, now),
+            epoch: 0,
+            ramp_start: now,
+        }
+    }
+}
+
+impl PlaybackEngine {
+    /// Replace the timeline. Loading a different source resets the transport to a
+    /// paused state at 0; reloading the same source (variant, speed or calibration
+    /// change) keeps the current transport so playback continues seamlessly.
+    pub fn load(&mut self, source: String, actions: Vec<Action>, intensity: Vec<Action>, now: Instant) {
+        if self.source.as_deref() != Some(source.as_str()) {
+            self.playing = false;
+            self.anchor_pos_ms = 0.0;
+            self.anchor_at = now;
+            self.fade = Fade::hold(0.0, now);
+        }
+
+        self.beats = beat_times(&actions);
+        self.max_intensity = intensity.iter().map(|a| a.pos).fold(0.0, f64::max).floor();
+        self.actions = actions;
+        self.intensity = intensity;
+        self.source = Some(source);
+        self.epoch += 1;
+    }
+
+    pub fn play(&mut self, at_ms: f64, now: Instant) {
+        self.manual = None;
+        if !self.playing {
+            self.fade = self.fade.towards(1.0, TRANSITION_DURATION, now);
+        }
+        self.playing = true;
+        self.set_anchor(at_ms, now);
+        self.epoch += 1;
+        self.ramp_start = now;
+    }
+
+    pub fn pause(&mut self, at_ms: f64, now: Instant) {
+        if self.playing {
+            self.fade = self.fade.towards(0.0, TRANSITION_DURATION, now);
+        }
+        self.playing = false;
+        self.set_anchor(at_ms, now);
+        self.epoch += 1;
+    }
+
+    /// Jump to a new position without changing the play state.
+    pub fn seek(&mut self, at_ms: f64, now: Instant) {
+        self.set_anchor(at_ms, now);
+        self.epoch += 1;
+        self.ramp_start = now;
+    }
+
+    /// Periodic timestamp report from the client while playing. Re-anchors the
+    /// extrapolated position so drift between server and video clock stays small.
+    pub fn sync(&mut self, at_ms: f64, now: Instant) {
+        self.set_anchor(at_ms, now);
+    }
+
+    /// Direct stroke override used by the calibration page. Cleared by `play`.
+    pub fn set_manual(&mut self, value: Option<f64>, now: Instant) {
+        self.manual = value.map(|v| v.clamp(0.0, 1.0));
+        self.manual_fade = Fade::hold(1.0, now);
+    }
+
+    /// Stop playback and ramp every output (including a manual override) down to
+    /// zero over `fade`. Used by the watchdog when the client goes silent.
+    pub fn halt(&mut self, fade: Duration, now: Instant) {
+        let pos = self.position_ms(now);
+        if self.playing {
+            self.fade = self.fade.towards(0.0, fade, now);
+        }
+        self.manual_fade = self.manual_fade.towards(0.0, fade, now);
+        self.playing = false;
+        self.set_anchor(pos, now);
+        self.epoch += 1;
+    }
+
+    /// Restart the soft-start ramp without touching the transport (used after re-arm).
+    pub fn restart_ramp(&mut self, now: Instant) {
+        self.ramp_start = now;
+    }
+
+    pub fn ramp_start(&self) -> Instant {
+        self.ramp_start
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Whether the engine may currently drive devices: playing, or holding a manual
+    /// override that has not been halted.
+    pub fn is_active(&self) -> bool {
+        self.playing || (self.manual.is_some() && self.manual_fade.to > 0.0)
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// Extrapolated script position in milliseconds.
+    pub fn position_ms(&self, now: Instant) -> f64 {
+        if self.playing {
+            self.anchor_pos_ms + now.saturating_duration_since(self.anchor_at).as_secs_f64() * 1000.0
+        } else {
+            self.anchor_pos_ms
+        }
+    }
+
+    /// Stroke hits after `from_ms`, up to `to_ms`.
+    pub fn beats_between(&self, from_ms: f64, to_ms: f64) -> &[u64] {
+        let start = self.beats.partition_point(|&b| (b as f64) <= from_ms);
+        let end = self.beats.partition_point(|&b| (b as f64) <= to_ms);
+        &self.beats[start..end.max(start)]
+    }
+
+    pub fn output(&self, now: Instant) -> PlaybackOutput {
+        if let Some(manual) = self.manual {
+            return PlaybackOutput {
+                stroke: manual * self.manual_fade.level(now),
+                ..Default::default()
+            };
+        }
+
+        let pos = self.position_ms(now);
+        let linear = self.linear_target(pos);
+
+        let level = self.fade.level(now);
+        if level <= 0.0 || self.intensity.is_empty() {
+            return PlaybackOutput {
+                linear,
+                ..Default::default()
+            };
+        }
+
+        let intensity = intensity_at(&self.intensity, pos);
+
+        let raw_rate = (intensity / 100.0) * self.max_intensity / 100.0 * level;
+        let rate_vibration = if raw_rate < VIBRATE_DEADZONE {
+            0.0
+        } else {
+            (raw_rate - VIBRATE_DEADZONE) * VIBRATE_SCALE
+        };
+
+        PlaybackOutput {
+            stroke: (intensity / 100.0 * level).clamp(0.0, 1.0),
+            rate_vibration: rate_vibration.clamp(0.0, 1.0),
+            beat_vibration: (beat_value_at(&self.beats, pos) * level).clamp(0.0, 1.0),
+            linear,
+            clockwise: stroke_is_upward(&self.actions, pos),
+        }
+    }
+
+    fn linear_target(&self, pos_ms: f64) -> Option<LinearTarget> {
+        if !self.playing {
+            return None;
+        }
+
+        let action_index = self.actions.partition_point(|a| (a.at as f64) <= pos_ms);
+        let action = self.actions.get(action_index)?;
+        Some(LinearTarget {
+            action_index,
+            epoch: self.epoch,
+            duration_ms: (action.at as f64 - pos_ms).max(1.0) as u32,
+            position: (action.pos / 100.0).clamp(0.0, 1.0),
+        })
+    }
+
+    fn set_anchor(&mut self, at_ms: f64, now: Instant) {
+        self.anchor_pos_ms = if at_ms.is_finite() { at_ms.max(0.0) } else { 0.0 };
+        self.anchor_at = now;
+    }
+}
+
+/// Linearly interpolate the intensity curve at `pos_ms`, holding the first and
+/// last values outside the curve.
+pub fn intensity_at(curve: &[Action], pos_ms: f64) -> f64 {
+    let idx = curve.partition_point(|a| (a.at as f64) <= pos_ms);
+
+    if idx == 0 {
+        return curve.first().map(|a| a.pos).unwrap_or(0.0);
+    }
+    if idx == curve.len() {
+        return curve[idx - 1].pos;
+    }
+
+    let prev = &curve[idx - 1];
+    let next = &curve[idx];
+    let span = next.at as f64 - prev.at as f64;
+    if span <= 0.0 {
+        return prev.pos;
+    }
+    let t = (pos_ms - prev.at as f64) / span;
+    prev.pos + t * (next.pos - prev.pos)
+}
+
+/// Direction of the stroke in progress at `pos_ms`. Holds the last movement between
+/// actions at the same position and before/after the script.
+fn stroke_is_upward(actions: &[Action], pos_ms: f64) -> bool {
+    let idx = actions.partition_point(|a| (a.at as f64) <= pos_ms).clamp(1, actions.len().max(1));
+    actions[..actions.len().min(idx + 1)]
+        .windows(2)
+        .rev()
+        .find(|pair| pair[0].pos != pair[1].pos)
+        .map(|pair| pair[1].pos > pair[0].pos)
+        .unwrap_or(true)
+}
+
+/// Timestamps of every stroke hit: the bottom of a down-stroke of at least
+/// `MIN_STROKE_DEPTH` where the script turns back up. Holds at the bottom are skipped.
+fn beat_times(actions: &[Action]) -> Vec<u64> {
+    let depths = stroke_depths(actions);
+    let mut bottom = None;
+    let mut beats = Vec::new();
+    for (pair, depth) in actions.windows(2).zip(depths) {
+        if pair[1].pos < pair[0].pos {
+            bottom = (depth >= MIN_STROKE_DEPTH).then_some(pair[1].at);
+        } else if pair[1].pos > pair[0].pos
+            && let Some(at) = bottom.take()
+        {
+            beats.push(at);
+        }
+    }
+    beats
+}
+
+/// Beat pulse that starts at 1.0 on each hit and decays towards the next one.
+fn beat_value_at(beats: &[u64], pos_ms: f64) -> f64 {
+    let idx = beats.partition_point(|&b| (b as f64) <= pos_ms);
+    if idx == 0 {
+        return 0.0;
+    }
+
+    let last = beats[idx - 1] as f64;
+    let next = beats.get(idx).map(|&b| b as f64).unwrap_or(last + BEAT_FALLBACK_MS);
+    let t = ((pos_ms - last) / (next - last)).clamp(0.0, 1.0);
+    1.0 - t.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(positions: &[f64]) -> Vec<Action> {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(i, &pos)| Action {
+                at: i as u64 * 100,
+                pos,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn beats_fall_on_the_bottom_of_each_stroke() {
+        // A stroke between 20 and 80 never reaches 0 or 100
+        assert_eq!(beat_times(&script(&[20.0, 80.0, 20.0, 80.0, 20.0, 80.0])), [200, 400]);
+        // The hit is where the bottom is reached, not where the script leaves it
+        assert_eq!(beat_times(&script(&[100.0, 0.0, 0.0, 100.0, 0.0, 100.0])), [100, 400]);
+        // A down-stroke the script does not turn back up from is not a hit yet
+        assert_eq!(beat_times(&script(&[0.0, 100.0, 0.0])), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn shallow_strokes_are_not_beats() {
+        let shallow = MIN_STROKE_DEPTH - 1.0;
+        let positions = [80.0, 20.0, 20.0 + shallow, 20.0, 80.0, 80.0 - shallow, 80.0, 20.0, 80.0];
+        assert_eq!(beat_times(&script(&positions)), [100, 700]);
+    }
+}
@@ -0,0 +1,438 @@
+// src/buttplug/session_recording.rs
+
+//! Recording and replay of device output
+//!
+//! While a recording runs, the control loop appends a `RecordedSample` whenever the
+//! values the devices received change or a stroke hit passes. Each sample holds the time
+//! since the recording started, the script position and every scalar, rotate and linear
+//! value after limits, curves and calibration. The header keeps the video, variant, speed
+//! mode and calibration profile the session was played with. Recordings are saved as
+//! JSON files in the .sessions directory under FUNSCRIPT_SHARE_PATH.
+//!
+//! A `Replay` plays the samples back through the control loop instead of the playback
+//! engine, so the session can be reproduced without the video. Devices are matched by
+//! name first and by index otherwise.
+
+use crate::buttplug::device_settings::ActuatorKind;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env,
+    io::ErrorKind,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tokio::fs;
+
+const SESSIONS_DIR_NAME: &str = ".sessions";
+/// Samples kept per recording (several hours of playback).
+pub const MAX_SAMPLES: usize = 200_000;
+
+/// Replayed value and rotation direction keyed by (device index, command family,
+/// actuator index).
+pub type ReplayValues = HashMap<(u32, ActuatorKind, u32), (f64, bool)>;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedDevice {
+    pub index: u32,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SessionMeta {
+    /// Unix time (ms) at which the recording started.
+    pub started_at: u64,
+    pub video: Option<String>,
+    pub variant: Option<String>,
+    pub speed: Option<String>,
+    pub calibration_profile: Option<String>,
+    /// Active calibration as (BPM, intensity) points.
+    pub calibration_points: Vec<(f64, f64)>,
+    pub devices: Vec<RecordedDevice>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedValue {
+    pub device: u32,
+    pub kind: ActuatorKind,
+    pub index: u32,
+    /// Speed, or position for linear actuators (0.0 .. 1.0).
+    pub value: f64,
+    /// Rotation direction; only set for rotate actuators.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clockwise: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedSample {
+    /// Milliseconds since the recording started.
+    pub t_ms: u64,
+    pub position_ms: f64,
+    pub playing: bool,
+    /// Script times (ms) of the stroke hits passed since the previous sample.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub beats: Vec<u64>,
+    pub values: Vec<RecordedValue>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionRecording {
+    pub meta: SessionMeta,
+    pub samples: Vec<RecordedSample>,
+}
+
+impl SessionRecording {
+    /// Length of the recording in milliseconds.
+    pub fn duration_ms(&self) -> u64 {
+        self.samples.last().map(|s| s.t_ms).unwrap_or(0)
+    }
+}
+
+/// A recording in progress.
+pub struct Recorder {
+    started: Instant,
+    recording: SessionRecording,
+}
+
+impl Recorder {
+    pub fn new(meta: SessionMeta, now: Instant) -> Self {
+        Self {
+            started: now,
+            recording: SessionRecording {
+                meta,
+                samples: Vec::new(),
+            },
+        }
+    }
+
+    /// Append a sample unless nothing changed since the previous one. Samples beyond
+    /// `MAX_SAMPLES` are dropped.
+    pub fn push(&mut self, now: Instant, position_ms: f64, playing: bool, beats: Vec<u64>, values: Vec<RecordedValue>) {
+        if self.recording.samples.len() >= MAX_SAMPLES {
+            return;
+        }
+        let unchanged = self
+            .recording
+            .samples
+            .last()
+            .is_some_and(|last| last.playing == playing && last.values == values && beats.is_empty());
+        if !unchanged {
+            self.recording.samples.push(RecordedSample {
+                t_ms: now.saturating_duration_since(self.started).as_millis() as u64,
+                position_ms,
+                playing,
+                beats,
+                values,
+            });
+            if self.recording.samples.len() == MAX_SAMPLES {
+                warn!("Session recording is full, later output is not recorded");
+            }
+        }
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.recording.samples.len()
+    }
+
+    /// End the recording at `now`, repeating the last values so a replay holds them until
+    /// the same point.
+    pub fn finish(mut self, now: Instant) -> SessionRecording {
+        let t_ms = now.saturating_duration_since(self.started).as_millis() as u64;
+        if let Some(last) = self.recording.samples.last()
+            && last.t_ms < t_ms
+        {
+            let last = RecordedSample {
+                t_ms,
+                beats: Vec::new(),
+                ..last.clone()
+            };
+            self.recording.samples.push(last);
+        }
+        self.recording
+    }
+}
+
+/// A recording being played back.
+pub struct Replay {
+    pub name: String,
+    recording: SessionRecording,
+    /// Recorded device index -> current device index.
+    device_map: HashMap<u32, u32>,
+    start_ms: u64,
+    started: Instant,
+    /// Start and length of the fade-out begun by `halt`.
+    fade: Option<(Instant, Duration)>,
+}
+
+impl Replay {
+    /// Start playing `recording` from `from_ms` into the recording. `devices` are the
+    /// currently connected devices as (index, name).
+    pub fn new(name: String, recording: SessionRecording, from_ms: u64, devices: &[(u32, String)], now: Instant) -> Self {
+        let mut device_map = HashMap::new();
+        let mut unused: Vec<&(u32, String)> = devices.iter().collect();
+        for recorded in &recording.meta.devices {
+            let by_name = unused.iter().position(|(_, name)| *name == recorded.name);
+            let by_index = || unused.iter().position(|(index, _)| *index == recorded.index);
+            if let Some(pos) = by_name.or_else(by_index) {
+                device_map.insert(recorded.index, unused.remove(pos).0);
+            }
+        }
+
+        Self {
+            name,
+            recording,
+            device_map,
+            start_ms: from_ms,
+            started: now,
+            fade: None,
+        }
+    }
+
+    /// Hold the current values and ramp them down to zero over `fade`; the replay ends once
+    /// the fade is done. Returns false if the replay was already halted.
+    pub fn halt(&mut self, fade: Duration, now: Instant) -> bool {
+        if self.fade.is_some() {
+            return false;
+        }
+        self.fade = Some((now, fade));
+        true
+    }
+
+    /// Output level of the fade-out at `now` (1.0 when not halted).
+    fn fade_level(&self, now: Instant) -> f64 {
+        match self.fade {
+            None => 1.0,
+            Some((_, duration)) if duration.is_zero() => 0.0,
+            Some((start, duration)) => {
+                let elapsed = now.saturating_duration_since(start).as_secs_f64();
+                1.0 - (elapsed / duration.as_secs_f64()).clamp(0.0, 1.0)
+            }
+        }
+    }
+
+    /// Recording time currently being played.
+    pub fn position_ms(&self, now: Instant) -> u64 {
+        self.start_ms + now.saturating_duration_since(self.started).as_millis() as u64
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.recording.duration_ms())
+    }
+
+    /// Values to send at `now`, keyed by current device, kind and index with the rotation
+    /// direction. `None` once the recording has ended or a halt has faded out.
+    pub fn values_at(&self, now: Instant) -> Option<ReplayValues> {
+        let level = self.fade_level(now);
+        let t = self.position_ms(self.fade.map_or(now, |(start, _)| start.min(now)));
+        if t > self.recording.duration_ms() || level <= 0.0 {
+            return None;
+        }
+        let idx = self.recording.samples.partition_point(|s| s.t_ms <= t);
+        let Some(sample) = idx.checked_sub(1).map(|i| &self.recording.samples[i]) else {
+            return Some(HashMap::new());
+        };
+
+        Some(
+            sample
+                .values
+                .iter()
+                .filter_map(|v| {
+                    let device = *self.device_map.get(&v.device)?;
+                    Some(((device, v.kind, v.index), (v.value * level, v.clockwise.unwrap_or(true))))
+                })
+                .collect(),
+        )
+    }
+}
+
+fn sessions_dir() -> PathBuf {
+    let base_dir = env::var("FUNSCRIPT_SHARE_PATH").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(base_dir).join(SESSIONS_DIR_NAME)
+}
+
+/// Recording names are plain file stems; anything else could escape the sessions directory.
+fn session_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid session name {name:?}"));
+    }
+    Ok(sessions_dir().join(format!("{name}.json")))
+}
+
+pub async fn write_session_file(name: &str, recording: &SessionRecording) -> Result<(), String> {
+    let path = session_path(name)?;
+    let json = serde_json::to_string(recording).map_err(|e| format!("Failed to serialize session: {e}"))?;
+
+    fs::create_dir_all(sessions_dir())
+        .await
+        .map_err(|e| format!("Failed to create directory {:?}: {e}", sessions_dir()))?;
+    fs::write(&path, json)
+        .await
+        .map_err(|e| format!("Failed to write session file {:?}: {e}", path))
+}
+
+pub async fn read_session_file(name: &str) -> Result<SessionRecording, String> {
+    let path = session_path(name)?;
+    let raw_json = fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read session file {:?}: {e}", path))?;
+    serde_json::from_str(&raw_json).map_err(|e| format!("Failed to parse session JSON: {e}"))
+}
+
+/// Names of the saved sessions, newest first.
+pub async fn list_session_files() -> Result<Vec<String>, String> {
+    let mut entries = match fs::read_dir(sessions_dir()).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read sessions directory: {e}")),
+    };
+
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json")
+            && let Some(stem) = path.file_stem()
+        {
+            names.push(stem.to_string_lossy().to_string());
+        }
+    }
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(device: u32, value: f64) -> RecordedValue {
+        RecordedValue {
+            device,
+            kind: ActuatorKind::Scalar,
+            index: 0,
+            value,
+            clockwise: None,
+        }
+    }
+
+    fn device(index: u32, name: &str) -> RecordedDevice {
+        RecordedDevice {
+            index,
+            name: name.to_string(),
+        }
+    }
+
+    /// Recording of device 1 stepping through `steps` as (t_ms, value).
+    fn recording(steps: &[(u64, f64)]) -> SessionRecording {
+        let mut meta = SessionMeta::default();
+        meta.devices.push(device(1, "Machine"));
+        let samples = steps
+            .iter()
+            .map(|&(t_ms, value)| RecordedSample {
+                t_ms,
+                position_ms: t_ms as f64,
+                playing: true,
+                beats: Vec::new(),
+                values: vec![scalar(1, value)],
+            })
+            .collect();
+        SessionRecording { meta, samples }
+    }
+
+    fn value_at(replay: &Replay, device: u32, at: Instant) -> Option<f64> {
+        replay.values_at(at).map(|values| values[&(device, ActuatorKind::Scalar, 0)].0)
+    }
+
+    #[test]
+    fn recorder_skips_unchanged_samples_and_holds_the_last_values() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut recorder = Recorder::new(SessionMeta::default(), start);
+        recorder.push(at(0), 0.0, true, Vec::new(), vec![scalar(1, 0.2)]);
+        recorder.push(at(50), 50.0, true, Vec::new(), vec![scalar(1, 0.2)]);
+        recorder.push(at(100), 100.0, true, vec![100], vec![scalar(1, 0.2)]);
+        recorder.push(at(150), 150.0, true, Vec::new(), vec![scalar(1, 0.6)]);
+        assert_eq!(recorder.sample_count(), 3);
+
+        let recording = recorder.finish(at(400));
+        let times: Vec<u64> = recording.samples.iter().map(|s| s.t_ms).collect();
+        assert_eq!(times, [0, 100, 150, 400]);
+        assert_eq!(recording.samples[1].beats, [100]);
+        assert_eq!(recording.samples[3].values, [scalar(1, 0.6)]);
+        assert!(recording.samples[3].beats.is_empty());
+        assert_eq!(recording.duration_ms(), 400);
+    }
+
+    #[test]
+    fn replay_maps_devices_by_name_then_index() {
+        let mut recording = recording(&[(0, 0.5), (100, 0.5)]);
+        recording.meta.devices = vec![device(1, "Machine"), device(2, "Renamed"), device(3, "Gone")];
+        recording.samples[0].values = vec![scalar(1, 0.1), scalar(2, 0.2), scalar(3, 0.3)];
+        let devices = [(7, "Machine".to_string()), (2, "New name".to_string()), (1, "Spare".to_string())];
+        let now = Instant::now();
+        let replay = Replay::new("test".to_string(), recording, 0, &devices, now);
+
+        // "Spare" took over index 1, but "Machine" follows its name; "Gone" is dropped
+        let values = replay.values_at(now).unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[&(7, ActuatorKind::Scalar, 0)], (0.1, true));
+        assert_eq!(values[&(2, ActuatorKind::Scalar, 0)], (0.2, true));
+    }
+
+    #[test]
+    fn replay_holds_each_sample_until_the_next_and_ends_on_its_own() {
+        let now = Instant::now();
+        let at = |ms| now + Duration::from_millis(ms);
+        let replay = Replay::new(
+            "test".to_string(),
+            recording(&[(100, 0.2), (300, 0.5), (600, 0.8)]),
+            0,
+            &[(1, "Machine".to_string())],
+            now,
+        );
+
+        assert_eq!(replay.values_at(at(50)), Some(HashMap::new()));
+        assert_eq!(value_at(&replay, 1, at(100)), Some(0.2));
+        assert_eq!(value_at(&replay, 1, at(299)), Some(0.2));
+        assert_eq!(value_at(&replay, 1, at(300)), Some(0.5));
+        assert_eq!(value_at(&replay, 1, at(600)), Some(0.8));
+        assert_eq!(replay.values_at(at(601)), None);
+        assert_eq!(replay.duration(), Duration::from_millis(600));
+    }
+
+    #[test]
+    fn replay_seeks_to_from_ms() {
+        let now = Instant::now();
+        let at = |ms| now + Duration::from_millis(ms);
+        let replay = Replay::new(
+            "test".to_string(),
+            recording(&[(100, 0.2), (300, 0.5), (600, 0.8)]),
+            350,
+            &[(1, "Machine".to_string())],
+            now,
+        );
+
+        assert_eq!(replay.position_ms(now), 350);
+        assert_eq!(value_at(&replay, 1, now), Some(0.5));
+        assert_eq!(value_at(&replay, 1, at(250)), Some(0.8));
+        assert_eq!(replay.values_at(at(251)), None);
+    }
+
+    #[test]
+    fn halted_replay_holds_its_values_and_fades_out() {
+        let now = Instant::now();
+        let at = |ms| now + Duration::from_millis(ms);
+        let mut replay = Replay::new(
+            "test".to_string(),
+            recording(&[(0, 0.4), (300, 0.8), (600, 0.8)]),
+            0,
+            &[(1, "Machine".to_string())],
+            now,
+        );
+
+        assert!(replay.halt(Duration::from_millis(200), at(100)));
+        assert!(!replay.halt(Duration::from_millis(200), at(150)));
+        assert_eq!(value_at(&replay, 1, at(100)), Some(0.4));
+        assert_eq!(value_at(&replay, 1, at(200)), Some(0.2));
+        assert_eq!(replay.values_at(at(300)), None);
+    }
+}
@@ -0,0 +1,168 @@
+// src/buttplug/virtual_device.rs
+
+//! Simulated output device
+//!
+//! A `VirtualDevice` stands in for real hardware when the output backend is set to
+//! `virtual`. It exposes one actuator of every kind the player can drive (oscillate,
+//! vibrate, linear position, rotate), keeps the last value of each and records every
+//! command it receives with the time of the control tick that issued it, so what the
+//! hardware would have received for a given funscript and playback sequence can be
+//! inspected through the API.
+//!
+//! The recording is a ring buffer; the oldest commands are dropped once it is full.
+
+use crate::buttplug::{device_settings::ActuatorKind, output::ActuatorSpec};
+use buttplug::core::message::ActuatorType;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::RwLock,
+    time::Instant,
+};
+
+/// Device index used for the virtual device; kept clear of the indices Intiface assigns
+/// so its stored settings never collide with a real device.
+pub const VIRTUAL_DEVICE_INDEX: u32 = 1000;
+const VIRTUAL_DEVICE_NAME: &str = "Virtual Machine";
+const MAX_RECORDED_COMMANDS: usize = 10_000;
+/// Resolution reported for every virtual actuator, about what common hardware offers.
+const VIRTUAL_STEP_COUNT: u32 = 20;
+
+/// One command as received by the virtual device.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VirtualCommand {
+    /// Values keyed by scalar actuator index.
+    Scalar { values: BTreeMap<u32, f64> },
+    /// (duration ms, position) keyed by linear actuator index.
+    Linear { values: BTreeMap<u32, (u32, f64)> },
+    /// (speed, clockwise) keyed by rotate actuator index.
+    Rotate { values: BTreeMap<u32, (f64, bool)> },
+    Stop,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecordedCommand {
+    /// Sequence number, increasing by one per command since the device was created.
+    pub seq: u64,
+    /// Milliseconds from the device's creation to the control tick that sent the command.
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub command: VirtualCommand,
+}
+
+/// Last value of every actuator, for display.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct VirtualState {
+    pub scalar: BTreeMap<u32, f64>,
+    pub linear: BTreeMap<u32, f64>,
+    pub rotate: BTreeMap<u32, (f64, bool)>,
+}
+
+#[derive(Default)]
+struct Recording {
+    next_seq: u64,
+    commands: VecDeque<RecordedCommand>,
+    state: VirtualState,
+}
+
+pub struct VirtualDevice {
+    created_at: Instant,
+    recording: RwLock<Recording>,
+}
+
+impl Default for VirtualDevice {
+    fn default() -> Self {
+        Self {
+            created_at: Instant::now(),
+            recording: RwLock::new(Recording::default()),
+        }
+    }
+}
+
+impl VirtualDevice {
+    pub fn index(&self) -> u32 {
+        VIRTUAL_DEVICE_INDEX
+    }
+
+    pub fn name(&self) -> &str {
+        VIRTUAL_DEVICE_NAME
+    }
+
+    /// The virtual battery never drains.
+    pub fn battery_level(&self) -> f64 {
+        1.0
+    }
+
+    pub fn actuators(&self, kind: ActuatorKind) -> Vec<ActuatorSpec> {
+        let spec = |actuator_type, description: &str| ActuatorSpec {
+            actuator_type,
+            step_count: VIRTUAL_STEP_COUNT,
+            description: description.to_string(),
+        };
+        match kind {
+            ActuatorKind::Scalar => vec![
+                spec(ActuatorType::Oscillate, "Virtual oscillator"),
+                spec(ActuatorType::Vibrate, "Virtual vibrator"),
+            ],
+            ActuatorKind::Linear => vec![spec(ActuatorType::Position, "Virtual stroker")],
+            ActuatorKind::Rotate => vec![spec(ActuatorType::Rotate, "Virtual rotator")],
+        }
+    }
+
+    pub fn scalar(&self, values: HashMap<u32, (f64, ActuatorType)>, at: Instant) {
+        let values = values.into_iter().map(|(i, (v, _))| (i, v)).collect();
+        self.record(VirtualCommand::Scalar { values }, at);
+    }
+
+    pub fn linear(&self, values: HashMap<u32, (u32, f64)>, at: Instant) {
+        let values = values.into_iter().collect();
+        self.record(VirtualCommand::Linear { values }, at);
+    }
+
+    pub fn rotate(&self, values: HashMap<u32, (f64, bool)>, at: Instant) {
+        let values = values.into_iter().collect();
+        self.record(VirtualCommand::Rotate { values }, at);
+    }
+
+    pub fn stop(&self, at: Instant) {
+        self.record(VirtualCommand::Stop, at);
+    }
+
+    pub fn state(&self) -> VirtualState {
+        self.recording.read().unwrap_or_else(|e| e.into_inner()).state.clone()
+    }
+
+    /// Recorded commands with a sequence number of at least `since_seq`, oldest first.
+    pub fn commands_since(&self, since_seq: u64) -> Vec<RecordedCommand> {
+        let recording = self.recording.read().unwrap_or_else(|e| e.into_inner());
+        recording
+            .commands
+            .iter()
+            .filter(|c| c.seq >= since_seq)
+            .cloned()
+            .collect()
+    }
+
+    fn record(&self, command: VirtualCommand, at: Instant) {
+        let at_ms = at.saturating_duration_since(self.created_at).as_millis() as u64;
+        let mut recording = self.recording.write().unwrap_or_else(|e| e.into_inner());
+
+        let state = &mut recording.state;
+        match &command {
+            VirtualCommand::Scalar { values } => state.scalar.extend(values),
+            VirtualCommand::Linear { values } => state.linear.extend(values.iter().map(|(i, (_, pos))| (*i, *pos))),
+            VirtualCommand::Rotate { values } => state.rotate.extend(values),
+            VirtualCommand::Stop => {
+                state.scalar.values_mut().for_each(|v| *v = 0.0);
+                state.rotate.values_mut().for_each(|v| v.0 = 0.0);
+            }
+        }
+        let seq = recording.next_seq;
+        recording.next_seq += 1;
+        if recording.commands.len() >= MAX_RECORDED_COMMANDS {
+            recording.commands.pop_front();
+        }
+        recording.commands.push_back(RecordedCommand { seq, at_ms, command });
+    }
+}
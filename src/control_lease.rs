@@ -0,0 +1,148 @@
+// src/control_lease.rs
+
+//! Control lease shared by the /ws sessions
+//!
+//! Every WebSocket connection is registered here with its own session id. At most one
+//! session holds the control lease and may drive the devices; every other session is an
+//! observer that still receives state and events. A free lease goes to the first session
+//! that sends a driving command, so a single tab never has to ask for it. Another session
+//! takes it over explicitly with `take_control`, and it is freed when the controller
+//! releases it or disconnects.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{collections::HashMap, sync::Mutex};
+use tokio::sync::broadcast;
+
+pub type SessionId = u64;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Controller,
+    Observer,
+}
+
+/// Who holds the lease, broadcast to every session whenever it changes.
+#[derive(Serialize, Debug, Clone)]
+pub struct LeaseState {
+    pub controller: Option<SessionId>,
+    /// Peer address of the controller.
+    pub controller_addr: Option<String>,
+    pub sessions: usize,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: SessionId,
+    controller: Option<SessionId>,
+    sessions: HashMap<SessionId, String>,
+}
+
+impl Registry {
+    fn state(&self) -> LeaseState {
+        LeaseState {
+            controller: self.controller,
+            controller_addr: self.controller.and_then(|id| self.sessions.get(&id).cloned()),
+            sessions: self.sessions.len(),
+        }
+    }
+}
+
+static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| Mutex::new(Registry::default()));
+static CHANGES: Lazy<broadcast::Sender<LeaseState>> = Lazy::new(|| broadcast::channel(16).0);
+
+/// Apply `change` to the registry and notify the sessions if it reports a change.
+fn update(change: impl FnOnce(&mut Registry) -> bool) -> bool {
+    let state = {
+        let mut registry = REGISTRY.lock().unwrap();
+        if !change(&mut registry) {
+            return false;
+        }
+        registry.state()
+    };
+    let _ = CHANGES.send(state);
+    true
+}
+
+/// Register a new connection from `addr`. It starts as an observer.
+pub fn register(addr: &str) -> SessionId {
+    let mut id = 0;
+    update(|registry| {
+        registry.next_id += 1;
+        id = registry.next_id;
+        registry.sessions.insert(id, addr.to_string());
+        true
+    });
+    id
+}
+
+/// Forget a closed connection, freeing the lease if it held it.
+pub fn unregister(id: SessionId) {
+    update(|registry| {
+        registry.sessions.remove(&id);
+        if registry.controller == Some(id) {
+            registry.controller = None;
+        }
+        true
+    });
+}
+
+pub fn role(id: SessionId) -> Role {
+    if REGISTRY.lock().unwrap().controller == Some(id) {
+        Role::Controller
+    } else {
+        Role::Observer
+    }
+}
+
+pub fn state() -> LeaseState {
+    REGISTRY.lock().unwrap().state()
+}
+
+/// Make sure `id` holds the lease before it drives the devices. A free lease is taken
+/// over; one held by another session is an error.
+pub fn acquire(id: SessionId) -> Result<(), String> {
+    let mut held_by_other = false;
+    update(|registry| match registry.controller {
+        Some(controller) if controller == id => false,
+        Some(_) => {
+            held_by_other = true;
+            false
+        }
+        None => {
+            registry.controller = Some(id);
+            true
+        }
+    });
+    if held_by_other {
+        return Err("Another client is in control; send take_control to take over".to_string());
+    }
+    Ok(())
+}
+
+/// Hand the lease to `id`, whoever held it before. Returns false if it already did.
+pub fn take_over(id: SessionId) -> bool {
+    update(|registry| {
+        if registry.controller == Some(id) || !registry.sessions.contains_key(&id) {
+            return false;
+        }
+        registry.controller = Some(id);
+        true
+    })
+}
+
+/// Give up the lease if `id` holds it.
+pub fn release(id: SessionId) -> bool {
+    update(|registry| {
+        if registry.controller != Some(id) {
+            return false;
+        }
+        registry.controller = None;
+        true
+    })
+}
+
+pub fn subscribe() -> broadcast::Receiver<LeaseState> {
+    CHANGES.subscribe()
+}
@@ -6,21 +6,26 @@
 //! for funscript files under a base directory. The cache is stored as a JSON file
 //! (.funscript_cache.json) beside the funscript base and maps relative file paths
 //! to computed entries (sha256, average/peak intensity, sample counts, timestamp).
+//! The file also records the BPM-to-intensity base table the stats were computed with;
+//! the cache is rebuilt when that table changes.
 
-use crate::buttplug::funscript_utils::{self, FunscriptData };
+use crate::buttplug::funscript_utils::{self, IntensityParams, LintSeverity};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::fs;
 use walkdir::WalkDir;
 
-const CURRENT_CACHE_VERSION: u32 = 2;
+const CURRENT_CACHE_VERSION: u32 = 4;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FunscriptCacheFile {
     pub version: u32,
+    /// Fingerprint of the base table the entries were computed with.
+    #[serde(default)]
+    pub bpm_table: String,
     pub entries: HashMap<String, FunscriptCacheEntry>,
 }
 
@@ -65,8 +70,13 @@ fn sha256_hex(bytes: &[u8]) -> String {
     hex::encode(hasher.finalize())
 }
 
+fn bpm_table_fingerprint() -> String {
+    let table = serde_json::to_string(&funscript_utils::bpm_table()).unwrap_or_default();
+    sha256_hex(table.as_bytes())
+}
+
 fn build_entry(content: &str, sha256: String) -> Result<FunscriptCacheEntry, String> {
-    let data: FunscriptData = serde_json::from_str(content)
+    let data = funscript_utils::parse_funscript(content)
         .map_err(|e| format!("Failed to parse funscript json: {}", e))?;
 
     if data.actions.len() < 2 {
@@ -81,9 +91,10 @@ fn build_entry(content: &str, sha256: String) -> Result<FunscriptCacheEntry, Str
     }
 
     let actions = data.actions.clone();
-    let intensity = funscript_utils::actions_to_intensity_curve(&actions, &[]);
+    let params = IntensityParams::default();
+    let intensity = funscript_utils::actions_to_intensity_curve(&actions, &[], &params);
     let (average_intensity, peak_intensity) = funscript_utils::calculate_intensity_stats(&intensity);
-    let volatility = funscript_utils::calculate_volatility(&actions); // <-- Added
+    let volatility = funscript_utils::calculate_volatility(&actions, &params); // <-- Added
 
     Ok(FunscriptCacheEntry {
         sha256,
@@ -95,15 +106,19 @@ fn build_entry(content: &str, sha256: String) -> Result<FunscriptCacheEntry, Str
     })
 }
 
-async fn read_cache(path: &Path) -> Result<FunscriptCache, String> {
+async fn read_cache(path: &Path, bpm_table: &str) -> Result<FunscriptCache, String> {
     match fs::read_to_string(path).await {
         Ok(raw) => {
             if let Ok(wrapper) = serde_json::from_str::<FunscriptCacheFile>(&raw) {
-                if wrapper.version == CURRENT_CACHE_VERSION {
-                    return Ok(wrapper.entries);
+                if wrapper.version != CURRENT_CACHE_VERSION {
+                    log::info!("Cache schema version changed (v{} -> v{}). Rebuilding cache...", wrapper.version, CURRENT_CACHE_VERSION);
+                    return Ok(HashMap::new());
                 }
-                log::info!("Cache schema version changed (v{} -> v{}). Rebuilding cache...", wrapper.version, CURRENT_CACHE_VERSION);
-                return Ok(HashMap::new());
+                if wrapper.bpm_table != bpm_table {
+                    log::info!("BPM-to-intensity table changed. Rebuilding cache...");
+                    return Ok(HashMap::new());
+                }
+                return Ok(wrapper.entries);
             }
             Ok(HashMap::new())
         }
@@ -112,12 +127,17 @@ async fn read_cache(path: &Path) -> Result<FunscriptCache, String> {
     }
 }
 
-async fn write_cache(path: &Path, cache: &FunscriptCache) -> Result<(), String> {
+async fn write_cache(path: &Path, cache: &FunscriptCache, bpm_table: &str) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         let _ = fs::create_dir_all(parent).await;
     }
 
-    let json = serde_json::to_string_pretty(cache).map_err(|e| format!("Ser failed: {}", e))?;
+    let wrapper = FunscriptCacheFile {
+        version: CURRENT_CACHE_VERSION,
+        bpm_table: bpm_table.to_string(),
+        entries: cache.clone(),
+    };
+    let json = serde_json::to_string_pretty(&wrapper).map_err(|e| format!("Ser failed: {}", e))?;
     fs::write(path, json)
         .await
         .map_err(|e| format!("Failed write cache {:?}: {}", path, e))
@@ -127,7 +147,8 @@ pub async fn scan_and_update_cache(
     base: &Path,
     cache_path: &Path,
 ) -> Result<FunscriptCache, String> {
-    let mut cache = read_cache(cache_path).await?;
+    let bpm_table = bpm_table_fingerprint();
+    let mut cache = read_cache(cache_path, &bpm_table).await?;
     let base_owned = base.to_path_buf();
     let discovered: Vec<(String, std::path::PathBuf)> = tokio::task::spawn_blocking(move || {
         let mut files = Vec::new();
@@ -155,18 +176,25 @@ pub async fn scan_and_update_cache(
         let sha = sha256_hex(content.as_bytes());
 
         // Skip if already cached with the same hash
-        if let Some(existing) = cache.get(key) {
-            if existing.sha256 == sha {
-                continue;
-            }
+        if let Some(existing) = cache.get(key)
+            && existing.sha256 == sha
+        {
+            continue;
         }
 
         match build_entry(&content, sha) {
             Ok(entry) => {
+                let errors = funscript_utils::lint_funscript(&content, None)
+                    .iter()
+                    .filter(|d| d.severity == LintSeverity::Error)
+                    .count();
+                if errors > 0 {
+                    log::warn!("{} fails {} lint checks (see /api/funscripts/lint?path={})", key, errors, key);
+                }
                 cache.insert(key.clone(), entry);
             }
             Err(e) => {
-                log::warn!("Skipping {}: {}", key, e);
+                log::warn!("Skipping {}: {} (see /api/funscripts/lint?path={})", key, e, key);
             }
         }
     }
@@ -174,7 +202,7 @@ pub async fn scan_and_update_cache(
     // Remove entries for files that no longer exist
     cache.retain(|k, _| seen_keys.contains(k));
 
-    write_cache(cache_path, &cache).await?;
+    write_cache(cache_path, &cache, &bpm_table).await?;
     Ok(cache)
 }
 
@@ -182,3 +210,12 @@ pub async fn get_cache_for_base(funscript_base: &Path) -> Result<FunscriptCache,
     let cache_path = funscript_base.join(".funscript_cache.json");
     scan_and_update_cache(funscript_base, &cache_path).await
 }
+
+/// Update the cache without waiting for it, e.g. after a funscript or the base table changed.
+pub fn refresh_cache_in_background(funscript_base: PathBuf) {
+    tokio::spawn(async move {
+        if let Err(e) = get_cache_for_base(&funscript_base).await {
+            log::error!("Funscript cache error: {}", e);
+        }
+    });
+}
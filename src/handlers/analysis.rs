@@ -20,7 +20,7 @@ struct DurationResult {
     delta: i64,
 }
 
-async fn get_video_duration_ffprobe(video_path: &Path) -> Option<u64> {
+pub async fn get_video_duration_ffprobe(video_path: &Path) -> Option<u64> {
     let output = tokio::process::Command::new("ffprobe")
         .args([
             "-v",
@@ -118,7 +118,7 @@ pub async fn handle_check_durations() -> impl Responder {
         });
     }
 
-    results.sort_by(|a, b| b.delta.cmp(&a.delta));
+    results.sort_by_key(|r| std::cmp::Reverse(r.delta));
 
     let mut rows_html = String::new();
     for item in &results {
@@ -0,0 +1,80 @@
+// src/handlers/auth.rs
+
+//! Login handler module
+//!
+//! Serves the login page and the endpoints that open and close a login session. The
+//! checks themselves live in `crate::auth`.
+
+use crate::auth;
+use actix_files::NamedFile;
+use actix_web::{
+    Error, HttpRequest, HttpResponse, Responder,
+    cookie::{Cookie, SameSite, time},
+    web,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const LOGIN_PAGE_PATH: &str = "./static/login.html";
+/// Delay before answering a failed login, to slow down guessing.
+const FAILED_LOGIN_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Deserialize)]
+pub struct LoginPayload {
+    /// Shared secret or personal token.
+    pub secret: String,
+}
+
+#[derive(Serialize)]
+pub struct AuthStatus {
+    pub enabled: bool,
+    pub user: Option<String>,
+}
+
+pub async fn handle_login_page() -> Result<impl Responder, Error> {
+    Ok(NamedFile::open(LOGIN_PAGE_PATH)?
+        .customize()
+        .insert_header(("Cache-Control", "no-cache")))
+}
+
+pub async fn get_status(req: HttpRequest) -> impl Responder {
+    HttpResponse::Ok().json(AuthStatus {
+        enabled: auth::config().enabled(),
+        user: auth::request_user(&req),
+    })
+}
+
+pub async fn login(payload: web::Json<LoginPayload>) -> impl Responder {
+    if !auth::config().enabled() {
+        return HttpResponse::BadRequest().body("Authentication is not enabled");
+    }
+
+    let Some((session_id, user)) = auth::login(payload.secret.trim()) else {
+        warn!("Rejected login attempt");
+        tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+        return HttpResponse::Unauthorized().body("Invalid secret or token");
+    };
+
+    info!("User {user} logged in");
+    let lifetime = auth::config().session_lifetime.as_secs() as i64;
+    let cookie = Cookie::build(auth::SESSION_COOKIE, session_id)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .max_age(time::Duration::seconds(lifetime))
+        .finish();
+    HttpResponse::Ok().cookie(cookie).json(AuthStatus {
+        enabled: true,
+        user: Some(user),
+    })
+}
+
+pub async fn logout(req: HttpRequest) -> impl Responder {
+    if let Some(cookie) = req.cookie(auth::SESSION_COOKIE) {
+        auth::logout(cookie.value());
+    }
+    let mut removal = Cookie::build(auth::SESSION_COOKIE, "").path("/").finish();
+    removal.make_removal();
+    HttpResponse::Ok().cookie(removal).json(serde_json::json!({ "ok": true }))
+}
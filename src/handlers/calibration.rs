@@ -6,8 +6,15 @@
 //! managing device calibration profiles. Profiles are stored as a JSON file
 //! (.calibration_profiles.json) under FUNSCRIPT_SHARE_PATH and map named
 //! profiles to per-range intensity actual measured BPMs.
-
-
+//!
+//! The BPM-to-intensity base table the profiles are measured against can be replaced
+//! through the API as well; it is stored in .bpm_table.json next to the profiles and
+//! loaded at startup. Changing it rebuilds the funscript cache.
+
+use crate::{
+    buttplug::funscript_utils::{self, BpmIntensityPoint},
+    funscript_cache,
+};
 use actix_files::NamedFile;
 use actix_web::{Error, HttpResponse, Responder, web};
 use log::{error, info};
@@ -19,6 +26,7 @@ type ProfileBpms = HashMap<String, f64>;
 pub type CalibrationProfiles = HashMap<String, ProfileBpms>;
 
 const CALIBRATION_FILE_NAME: &str = ".calibration_profiles.json";
+const BPM_TABLE_FILE_NAME: &str = ".bpm_table.json";
 const CALIBRATION_PAGE_PATH: &str = "./static/calibration.html";
 
 #[derive(Deserialize, Serialize)]
@@ -27,11 +35,21 @@ pub struct SaveProfilePayload {
     pub bpms: ProfileBpms,
 }
 
+#[derive(Deserialize)]
+pub struct BpmTablePayload {
+    pub points: Vec<BpmIntensityPoint>,
+}
+
 fn profile_store_path() -> PathBuf {
     let base_dir = env::var("FUNSCRIPT_SHARE_PATH").unwrap_or_else(|_| ".".to_string());
     PathBuf::from(base_dir).join(CALIBRATION_FILE_NAME)
 }
 
+fn bpm_table_path() -> PathBuf {
+    let base_dir = env::var("FUNSCRIPT_SHARE_PATH").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(base_dir).join(BPM_TABLE_FILE_NAME)
+}
+
 async fn read_profiles_file() -> Result<CalibrationProfiles, String> {
     let path = profile_store_path();
 
@@ -62,6 +80,33 @@ async fn write_profiles_file(profiles: &CalibrationProfiles) -> Result<(), Strin
         .map_err(|e| format!("Failed to write calibration profile file {:?}: {e}", path))
 }
 
+/// Load the saved base table, if any. Must run before the funscript cache is built.
+pub async fn load_bpm_table() {
+    let path = bpm_table_path();
+    let raw_json = match fs::read_to_string(&path).await {
+        Ok(raw_json) => raw_json,
+        Err(e) if e.kind() == ErrorKind::NotFound => return,
+        Err(e) => {
+            error!("Failed to read BPM table file {:?}, using the default table: {e}", path);
+            return;
+        }
+    };
+
+    let loaded = serde_json::from_str::<Vec<BpmIntensityPoint>>(&raw_json)
+        .map_err(|e| format!("Failed to parse BPM table JSON: {e}"))
+        .and_then(|points| funscript_utils::set_bpm_table(&points));
+    match loaded {
+        Ok(()) => info!("Loaded BPM-to-intensity table from {:?}", path),
+        Err(e) => error!("Ignoring BPM table file {:?}, using the default table: {e}", path),
+    }
+}
+
+fn refresh_funscript_cache() {
+    if let Ok(base) = env::var("FUNSCRIPT_SHARE_PATH") {
+        funscript_cache::refresh_cache_in_background(PathBuf::from(base));
+    }
+}
+
 pub async fn handle_calibration_page() -> Result<impl Responder, Error> {
     Ok(NamedFile::open(CALIBRATION_PAGE_PATH)?
         .customize()
@@ -70,20 +115,72 @@ pub async fn handle_calibration_page() -> Result<impl Responder, Error> {
 
 #[derive(Serialize)]
 pub struct BpmMappingResponse {
-    pub points: Vec<crate::buttplug::funscript_utils::BpmIntensityPoint>,
+    pub points: Vec<BpmIntensityPoint>,
     pub lut: HashMap<String, f64>,
 }
 
 pub async fn get_bpm_mapping() -> impl Responder {
-    let points = crate::buttplug::funscript_utils::get_bpm_intensity_mapping();
+    let points = funscript_utils::get_bpm_intensity_mapping();
     let mut lut = HashMap::new();
     for i in 0..=100 {
-        let bpm = crate::buttplug::funscript_utils::intensity_to_bpm(i as f64);
+        let bpm = funscript_utils::intensity_to_bpm(i as f64);
         lut.insert(i.to_string(), bpm);
     }
     HttpResponse::Ok().json(BpmMappingResponse { points, lut })
 }
 
+pub async fn get_bpm_table() -> impl Responder {
+    HttpResponse::Ok().json(funscript_utils::get_bpm_intensity_mapping())
+}
+
+pub async fn set_bpm_table(payload: web::Json<BpmTablePayload>) -> impl Responder {
+    let points = payload.into_inner().points;
+    if let Err(e) = funscript_utils::validate_bpm_table(&points) {
+        return HttpResponse::BadRequest().body(e);
+    }
+
+    let path = bpm_table_path();
+    let written = match serde_json::to_string_pretty(&points) {
+        Ok(json) => fs::write(&path, json).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = written {
+        error!("Failed to write BPM table file {:?}: {e}", path);
+        return HttpResponse::InternalServerError().body("Failed to save BPM table");
+    }
+
+    if let Err(e) = funscript_utils::set_bpm_table(&points) {
+        return HttpResponse::BadRequest().body(e);
+    }
+    info!("Saved BPM-to-intensity table with {} points", points.len());
+    refresh_funscript_cache();
+    HttpResponse::Ok().json(serde_json::json!({ "ok": true }))
+}
+
+pub async fn reset_bpm_table() -> impl Responder {
+    let path = bpm_table_path();
+    match fs::remove_file(&path).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => {
+            error!("Failed to remove BPM table file {:?}: {e}", path);
+            return HttpResponse::InternalServerError().body("Failed to reset BPM table");
+        }
+    }
+
+    let defaults: Vec<BpmIntensityPoint> = funscript_utils::DEFAULT_BPM_TO_INTENSITY
+        .iter()
+        .map(|&(bpm, intensity)| BpmIntensityPoint { bpm, intensity })
+        .collect();
+    if let Err(e) = funscript_utils::set_bpm_table(&defaults) {
+        error!("Default BPM table is invalid: {e}");
+        return HttpResponse::InternalServerError().body("Failed to reset BPM table");
+    }
+    info!("Reset BPM-to-intensity table to the default");
+    refresh_funscript_cache();
+    HttpResponse::Ok().json(serde_json::json!({ "ok": true }))
+}
+
 pub async fn get_profiles() -> impl Responder {
     match read_profiles_file().await {
         Ok(profiles) => HttpResponse::Ok().json(profiles),
@@ -125,6 +222,9 @@ pub async fn save_profile(payload: web::Json<SaveProfilePayload>) -> impl Respon
 #[derive(Deserialize)]
 pub struct SetActiveProfilePayload {
     pub bpms: HashMap<String, f64>,
+    /// Profile name, recorded with sessions.
+    #[serde(default)]
+    pub name: Option<String>,
 }
 
 pub async fn set_active_profile(payload: web::Json<SetActiveProfilePayload>) -> impl Responder {
@@ -138,17 +238,8 @@ pub async fn set_active_profile(payload: web::Json<SetActiveProfilePayload>) ->
 
     points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
     crate::buttplug::device_manager::set_active_calibration_points(points);
+    let name = payload.name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    crate::buttplug::device_manager::set_active_calibration_name(name.map(str::to_string));
 
     HttpResponse::Ok().json(serde_json::json!({ "ok": true }))
 }
-
-#[derive(Deserialize)]
-pub struct SetMaxLimitPayload {
-    pub limit: f64,
-}
-
-pub async fn set_max_limit(payload: web::Json<SetMaxLimitPayload>) -> impl Responder {
-    let normalized_limit = (payload.limit / 100.0).clamp(0.0, 1.0);
-    crate::buttplug::device_manager::set_max_limit(normalized_limit);
-    HttpResponse::Ok().json(serde_json::json!({ "ok": true }))
-}
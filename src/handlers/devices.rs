@@ -0,0 +1,247 @@
+// src/handlers/devices.rs
+
+//! Device registry handler module
+//!
+//! Reports the state of the Intiface connection, lists the devices currently
+//! connected together with their actuators and assigned roles, and lets the UI
+//! change the role, output curve and rise/fall limits of each actuator and the
+//! per-device output scale and soft start. The max limit endpoints set the global
+//! ceiling or the limit of single devices and actuators. Changes are persisted by
+//! the device manager. Also exposes the Intiface endpoint and loop timings for
+//! runtime adjustment; those changes last until the next restart. The emergency
+//! stop and command counter endpoints live here as well, and so does the command
+//! recording of the virtual device.
+
+use crate::buttplug::{
+    device_manager::{self, DeviceInfo},
+    device_settings::{ActuatorCurveUpdate, ActuatorKind, ActuatorRole, DeviceSettingsUpdate},
+    intiface_config::IntifaceConfigUpdate,
+};
+use actix_web::{HttpResponse, Responder, web};
+use log::{error, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub async fn get_connection() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "state": device_manager::get_connection_state()
+    }))
+}
+
+pub async fn get_stop_state() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "stopped": device_manager::is_stopped()
+    }))
+}
+
+/// Emergency stop. Latches until `rearm`, even if Intiface did not confirm the stop.
+pub async fn emergency_stop() -> impl Responder {
+    match device_manager::emergency_stop().await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "ok": true, "stopped": true })),
+        Err(e) => {
+            error!("Emergency stop could not reach Intiface: {e}");
+            HttpResponse::BadGateway().json(serde_json::json!({ "ok": false, "stopped": true, "error": e }))
+        }
+    }
+}
+
+pub async fn rearm() -> impl Responder {
+    device_manager::rearm();
+    HttpResponse::Ok().json(serde_json::json!({ "ok": true, "stopped": false }))
+}
+
+pub async fn get_intiface_config() -> impl Responder {
+    HttpResponse::Ok().json(device_manager::get_intiface_config())
+}
+
+pub async fn update_intiface_config(payload: web::Json<IntifaceConfigUpdate>) -> impl Responder {
+    match device_manager::update_intiface_config(payload.into_inner()) {
+        Ok(config) => HttpResponse::Ok().json(config),
+        Err(e) => {
+            warn!("Rejected Intiface configuration update: {e}");
+            HttpResponse::BadRequest().body(e)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct VirtualDeviceQuery {
+    /// Only return commands with a sequence number of at least this value.
+    #[serde(default)]
+    pub since: u64,
+}
+
+/// Current actuator values and recorded commands of the virtual device.
+pub async fn get_virtual_device(query: web::Query<VirtualDeviceQuery>) -> impl Responder {
+    match device_manager::virtual_device_snapshot(query.since) {
+        Some((state, commands)) => HttpResponse::Ok().json(serde_json::json!({
+            "state": state,
+            "commands": commands
+        })),
+        None => HttpResponse::NotFound().body("Virtual output backend is not active"),
+    }
+}
+
+/// Sent, skipped and failed command counts per device index, plus their total.
+pub async fn get_command_stats() -> impl Responder {
+    let devices = device_manager::command_counters();
+    let mut total = device_manager::CommandCounters::default();
+    devices.values().for_each(|c| total.add(c));
+    HttpResponse::Ok().json(serde_json::json!({
+        "total": total,
+        "devices": devices
+    }))
+}
+
+pub async fn get_devices() -> impl Responder {
+    HttpResponse::Ok().json(device_manager::list_devices().await)
+}
+
+#[derive(Deserialize)]
+pub struct UpdateDevicePayload {
+    /// Scalar actuator roles keyed by actuator index; actuators not listed keep their current role.
+    pub roles: Option<HashMap<u32, ActuatorRole>>,
+    /// Linear actuator roles keyed by actuator index.
+    pub linear_roles: Option<HashMap<u32, ActuatorRole>>,
+    /// Rotate actuator roles keyed by actuator index.
+    pub rotate_roles: Option<HashMap<u32, ActuatorRole>>,
+    /// Scalar actuator output curves keyed by actuator index.
+    pub curves: Option<HashMap<u32, CurvePayload>>,
+    /// Rotate actuator output curves keyed by actuator index.
+    pub rotate_curves: Option<HashMap<u32, CurvePayload>>,
+    /// Output scale in percent (0–100).
+    pub scale: Option<f64>,
+    /// Soft-start ramp after play, seek or re-arm, in milliseconds (0 = off).
+    pub soft_start_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct CurvePayload {
+    /// Highest output in percent (0–100).
+    pub limit: Option<f64>,
+    /// Lowest non-zero output in percent (0–100).
+    pub floor: Option<f64>,
+    /// Curve exponent (0.1–10, 1 = linear).
+    pub gamma: Option<f64>,
+    /// Acceleration limit in percent of full output per second (0 = unlimited).
+    pub max_rise: Option<f64>,
+    /// Deceleration limit in percent of full output per second (0 = unlimited).
+    pub max_fall: Option<f64>,
+}
+
+fn to_curve_updates(curves: Option<HashMap<u32, CurvePayload>>) -> Option<HashMap<u32, ActuatorCurveUpdate>> {
+    curves.map(|curves| {
+        curves
+            .into_iter()
+            .map(|(i, c)| {
+                let update = ActuatorCurveUpdate {
+                    limit: c.limit.map(|l| l / 100.0),
+                    floor: c.floor.map(|f| f / 100.0),
+                    gamma: c.gamma,
+                    max_rise_per_sec: c.max_rise.map(|r| r / 100.0),
+                    max_fall_per_sec: c.max_fall.map(|r| r / 100.0),
+                };
+                (i, update)
+            })
+            .collect()
+    })
+}
+
+impl UpdateDevicePayload {
+    /// Convert the percent-based payload into a normalized settings update.
+    pub fn into_update(self) -> DeviceSettingsUpdate {
+        DeviceSettingsUpdate {
+            roles: self.roles,
+            linear_roles: self.linear_roles,
+            rotate_roles: self.rotate_roles,
+            curves: to_curve_updates(self.curves),
+            rotate_curves: to_curve_updates(self.rotate_curves),
+            scale: self.scale.map(|s| s / 100.0),
+            soft_start_ms: self.soft_start_ms,
+        }
+    }
+}
+
+pub async fn update_device(
+    path: web::Path<u32>,
+    payload: web::Json<UpdateDevicePayload>,
+) -> impl Responder {
+    let device_index = path.into_inner();
+    let update = payload.into_inner().into_update();
+
+    match device_manager::update_device_settings(device_index, update).await {
+        Ok(info) => HttpResponse::Ok().json(info),
+        Err(e) => {
+            warn!("Failed to update device {device_index}: {e}");
+            HttpResponse::BadRequest().body(e)
+        }
+    }
+}
+
+pub async fn get_max_limit() -> impl Responder {
+    let devices: Vec<_> = device_manager::list_devices()
+        .await
+        .into_iter()
+        .map(|d| {
+            let actuators: Vec<_> = d
+                .actuators
+                .iter()
+                .filter_map(|a| {
+                    let curve = a.curve?;
+                    Some(serde_json::json!({ "kind": a.kind, "index": a.index, "limit": curve.limit * 100.0 }))
+                })
+                .collect();
+            serde_json::json!({ "index": d.index, "name": d.name, "actuators": actuators })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "limit": device_manager::get_max_limit() * 100.0,
+        "devices": devices
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct SetMaxLimitPayload {
+    /// Limit in percent (0–100).
+    pub limit: f64,
+    /// Device to limit; the global ceiling is set when missing.
+    pub device: Option<u32>,
+    /// Command family of the actuator to limit; every scalar and rotate actuator of the
+    /// device is limited when missing.
+    pub kind: Option<ActuatorKind>,
+    /// Actuator index within `kind`.
+    pub actuator: Option<u32>,
+}
+
+impl SetMaxLimitPayload {
+    /// Set the global ceiling, or the limit of the given device or actuator. Returns the
+    /// updated device when a device was targeted.
+    pub async fn apply(self) -> Result<Option<DeviceInfo>, String> {
+        let normalized_limit = (self.limit / 100.0).clamp(0.0, 1.0);
+        let Some(device_index) = self.device else {
+            device_manager::set_max_limit(normalized_limit);
+            return Ok(None);
+        };
+
+        let actuator = match (self.kind, self.actuator) {
+            (Some(kind), Some(index)) => Some((kind, index)),
+            (None, None) => None,
+            _ => return Err("kind and actuator must be given together".to_string()),
+        };
+        device_manager::set_device_max_limit(device_index, actuator, normalized_limit)
+            .await
+            .map(Some)
+    }
+}
+
+pub async fn set_max_limit(payload: web::Json<SetMaxLimitPayload>) -> impl Responder {
+    match payload.into_inner().apply().await {
+        Ok(Some(info)) => HttpResponse::Ok().json(info),
+        Ok(None) => HttpResponse::Ok().json(serde_json::json!({ "ok": true })),
+        Err(e) => {
+            warn!("Failed to set the max limit: {e}");
+            HttpResponse::BadRequest().body(e)
+        }
+    }
+}
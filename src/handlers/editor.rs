@@ -5,11 +5,14 @@
 //! Serves the in-browser funscript editor page and handles saving edited
 //! funscript data. Accepts a video path, action list, and optional variant
 //! name, validates inputs for path safety and variant format, then writes
-//! the resulting .funscript file under FUNSCRIPT_SHARE_PATH. Triggers a
-//! background cache refresh after successful writes.
+//! the resulting .funscript file under FUNSCRIPT_SHARE_PATH. When a script is
+//! rewritten, its version, `inverted`, `range` and metadata are kept and the
+//! positions are written in its convention. Triggers a background cache refresh
+//! after successful writes.
 
-use crate::buttplug::{device_manager::self, funscript_utils::{self, FunscriptData}};
+use crate::buttplug::{device_manager::self, funscript_utils::{self, Action, FunscriptData, IntensityParamsUpdate}};
 use crate::funscript_cache;
+use crate::handlers::intensity_presets;
 use actix_files::NamedFile;
 use actix_web::{Error, HttpResponse, Responder, web};
 use serde::Deserialize;
@@ -29,13 +32,24 @@ pub async fn handle_editor_page() -> Result<impl Responder, Error> {
 #[derive(Deserialize)]
 pub struct CalculateDraftIntensityPayload {
     pub taps: Vec<u64>,
+    /// Intensity parameter preset; the defaults when missing.
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// Overrides applied on top of the preset.
+    #[serde(default)]
+    pub params: IntensityParamsUpdate,
 }
 
 /// Provide intensity values for the 'draft' funscript the user is authoring in the editor page.
 pub async fn calculate_draft_intensity(
     payload: web::Json<CalculateDraftIntensityPayload>,
 ) -> impl Responder {
-    let taps = payload.into_inner().taps;
+    let payload = payload.into_inner();
+    let params = match intensity_presets::resolve_params(payload.preset.as_deref(), payload.params).await {
+        Ok(params) => params,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let taps = payload.taps;
     let actions = funscript_utils::generate_funscript_actions_from_taps(&taps);
 
     if taps.len() < 2 {
@@ -43,20 +57,22 @@ pub async fn calculate_draft_intensity(
             "peak": 0.0,
             "average": 0.0,
             "volatility": 0.0,
-            "actions": actions
+            "actions": actions,
+            "params": params
         }));
     }
 
     let cal_points = device_manager::get_active_calibration_points();
-    let intensity_curve = funscript_utils::actions_to_intensity_curve(&actions, &cal_points);
+    let intensity_curve = funscript_utils::actions_to_intensity_curve(&actions, &cal_points, &params);
     let (average, peak) = funscript_utils::calculate_intensity_stats(&intensity_curve);
-    let volatility = funscript_utils::calculate_volatility(&actions);
+    let volatility = funscript_utils::calculate_volatility(&actions, &params);
 
     HttpResponse::Ok().json(serde_json::json!({
         "peak": peak,
         "average": average,
         "volatility": volatility,
-        "actions": actions
+        "actions": actions,
+        "params": params
     }))
 }
 
@@ -92,10 +108,7 @@ pub async fn save_funscript(payload: web::Json<SaveFunscriptPayload>) -> impl Re
     let output_path = build_funscript_path(&share_path, &relative_video_path, variant.as_deref());
 
     let actions = funscript_utils::generate_funscript_actions_from_taps(&request.taps);
-    let funscript_data = FunscriptData {
-        actions,
-        ..Default::default()
-    };
+    let funscript_data = with_actions(read_existing_header(&output_path).await, actions);
 
     let funscript_json = match serde_json::to_string_pretty(&funscript_data) {
         Ok(json) => json,
@@ -105,12 +118,12 @@ pub async fn save_funscript(payload: web::Json<SaveFunscriptPayload>) -> impl Re
         }
     };
 
-    if let Some(parent_dir) = output_path.parent() {
-        if let Err(err) = fs::create_dir_all(parent_dir).await {
-            log::error!("Failed to create directory {:?}: {}", parent_dir, err);
-            return HttpResponse::InternalServerError()
-                .json("Failed to create directory for funscript.");
-        }
+    if let Some(parent_dir) = output_path.parent()
+        && let Err(err) = fs::create_dir_all(parent_dir).await
+    {
+        log::error!("Failed to create directory {:?}: {}", parent_dir, err);
+        return HttpResponse::InternalServerError()
+            .json("Failed to create directory for funscript.");
     }
 
     if let Err(err) = fs::write(&output_path, funscript_json).await {
@@ -118,19 +131,50 @@ pub async fn save_funscript(payload: web::Json<SaveFunscriptPayload>) -> impl Re
         return HttpResponse::InternalServerError().json("Failed to save funscript file.");
     }
 
-    refresh_cache_in_background(share_path);
+    funscript_cache::refresh_cache_in_background(share_path);
 
     log::info!("Saved funscript to {:?}", output_path);
     HttpResponse::Ok().json("Funscript saved successfully.")
 }
 
+/// Everything but the actions of the script at `path`, or the defaults for a new script.
+async fn read_existing_header(path: &Path) -> FunscriptData {
+    let Ok(content) = fs::read_to_string(path).await else {
+        return FunscriptData::default();
+    };
+    existing_header(&content).unwrap_or_else(|err| {
+        log::warn!("Overwriting unreadable funscript {:?}: {}", path, err);
+        FunscriptData::default()
+    })
+}
+
+fn existing_header(content: &str) -> Result<FunscriptData, serde_json::Error> {
+    let existing = serde_json::from_str::<FunscriptData>(content)?;
+    Ok(FunscriptData {
+        actions: Vec::new(),
+        ..existing
+    })
+}
+
+/// `header` with the normalised `actions` converted into its `inverted` / `range` convention.
+fn with_actions(header: FunscriptData, actions: Vec<Action>) -> FunscriptData {
+    let actions = actions
+        .into_iter()
+        .map(|action| Action {
+            pos: header.denormalized_position(action.pos),
+            ..action
+        })
+        .collect();
+    FunscriptData { actions, ..header }
+}
+
 fn read_share_path() -> Result<PathBuf, String> {
     env::var("FUNSCRIPT_SHARE_PATH")
         .map(PathBuf::from)
         .map_err(|err| format!("Server configuration error: FUNSCRIPT_SHARE_PATH not set ({err})"))
 }
 
-fn is_safe_relative_path(path: &Path) -> bool {
+pub fn is_safe_relative_path(path: &Path) -> bool {
     !path.is_absolute() && path.components().all(|c| matches!(c, Component::Normal(_)))
 }
 
@@ -161,8 +205,57 @@ fn build_funscript_path(root: &Path, relative_video_path: &Path, variant: Option
     path
 }
 
-fn refresh_cache_in_background(share_path: PathBuf) {
-    tokio::spawn(async move {
-        let _ = funscript_cache::get_cache_for_base(&share_path).await;
-    });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_keeps_inverted_range_and_metadata() {
+        let existing = r#"{
+            "version": "1.1",
+            "inverted": true,
+            "range": 90,
+            "metadata": {"creator": "someone"},
+            "actions": [{"at": 0, "pos": 90}, {"at": 500, "pos": 0}]
+        }"#;
+        let actions = funscript_utils::generate_funscript_actions_from_taps(&[1000, 1500, 2000, 2500]);
+
+        let header = existing_header(existing).unwrap();
+        let json = serde_json::to_string(&with_actions(header, actions.clone())).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(saved["version"], "1.1");
+        assert_eq!(saved["inverted"], true);
+        assert_eq!(saved["range"], 90);
+        assert_eq!(saved["metadata"]["creator"], "someone");
+
+        // Positions are stored in the script's convention: normalised 100 is 0 when inverted
+        let raw: Vec<f64> = saved["actions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["pos"].as_f64().unwrap())
+            .collect();
+        for (raw, action) in raw.iter().zip(&actions) {
+            assert!((raw - (100.0 - action.pos) * 0.9).abs() < 1e-9);
+        }
+
+        // Reading the saved script back gives the positions the editor produced
+        let reread = funscript_utils::parse_funscript(&json).unwrap();
+        assert_eq!(reread.actions.len(), actions.len());
+        for (read, action) in reread.actions.iter().zip(&actions) {
+            assert_eq!(read.at, action.at);
+            assert!((read.pos - action.pos).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn save_without_existing_script_uses_defaults() {
+        let actions = funscript_utils::generate_funscript_actions_from_taps(&[1000, 1500]);
+        let saved = with_actions(FunscriptData::default(), actions.clone());
+
+        assert!(!saved.inverted);
+        assert_eq!(saved.range, 100);
+        assert_eq!(saved.actions, actions);
+    }
 }
@@ -4,9 +4,11 @@
 //!
 //! This module handles requests for funscript files, which contain synchronized motion
 //! data for videos. It loads the original funscript and generates real-time intensity
-//! data used for device control.
+//! data used for device control. The intensity parameters can be chosen per request with
+//! `preset` and individual overrides (see `intensity_presets`).
 
-use crate::buttplug::{device_manager::self, funscript_utils::{self, FunscriptData}};
+use crate::buttplug::{device_manager, funscript_utils::{self, FunscriptData, IntensityParams}};
+use crate::handlers::intensity_presets;
 use actix_web::{HttpResponse, web};
 use log::{error, info, warn};
 use serde::Serialize;
@@ -25,6 +27,9 @@ pub struct FunscriptResponse {
     pub peak: f64,
     pub average: f64,
     pub volatility: f64,
+    /// Intensity parameters the curve was generated with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<IntensityParams>,
 }
 
 pub async fn handle_funscript(
@@ -51,23 +56,19 @@ pub async fn handle_funscript(
         .get("variant")
         .map(|s| s.as_str())
         .unwrap_or("original");
+    let speed = query.get("speed").map(|s| s.as_str()).unwrap_or("normal");
 
-    let funscript_path = build_funscript_path(&video_path, &base_path, variant);
-
-    let mut original_res = read_funscript(&funscript_path).await;
-
-    // Fallback: check parent directory if not found in current directory.
-    // Mainly for videos that have a 2D and a 3D SBS variant
-    if original_res.is_err() {
-        if let Some(parent_path) = build_parent_funscript_path(&video_path, &base_path, variant) {
-            if let Ok(data) = read_funscript(&parent_path).await {
-                original_res = Ok(data);
-            }
-        }
-    }
+    let params = match intensity_presets::update_from_query(&query) {
+        Ok(update) => intensity_presets::resolve_params(query.get("preset").map(|s| s.as_str()), update).await,
+        Err(e) => Err(e),
+    };
+    let params = match params {
+        Ok(params) => params,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
 
-    let mut original = match original_res {
-        Ok(data) => data,
+    let (original, intensity) = match load_funscript(&base_path, &video_path, variant, speed, &params).await {
+        Ok(loaded) => loaded,
         Err(e) => {
             info!("Funscript not found for {} (tried primary and parent): {}", video_path, e);
             return HttpResponse::NotFound().json(FunscriptResponse {
@@ -75,12 +76,53 @@ pub async fn handle_funscript(
                 intensity: None,
                 volatility: f64::NAN,
                 peak: f64::NAN,
-                average: f64::NAN
+                average: f64::NAN,
+                params: None,
             });
         }
     };
 
-    let speed = query.get("speed").map(|s| s.as_str()).unwrap_or("normal");
+    let (average, peak) = intensity
+        .as_ref()
+        .map(|i| funscript_utils::calculate_intensity_stats(&i.actions))
+        .unwrap_or((0.0, 0.0));
+
+    let volatility = funscript_utils::calculate_volatility(&original.actions, &params);
+
+    HttpResponse::Ok().json(FunscriptResponse {
+        original: Some(original),
+        intensity,
+        peak,
+        average,
+        volatility,
+        params: Some(params),
+    })
+}
+
+/// Load a funscript variant for `video_path`, apply the speed transform and generate the
+/// calibrated intensity curve. Shared by the HTTP handler and the playback engine.
+pub async fn load_funscript(
+    base_path: &str,
+    video_path: &str,
+    variant: &str,
+    speed: &str,
+    params: &IntensityParams,
+) -> Result<(FunscriptData, Option<FunscriptData>), String> {
+    let funscript_path = build_funscript_path(video_path, base_path, variant);
+
+    let mut original_res = read_funscript(&funscript_path).await;
+
+    // Fallback: check parent directory if not found in current directory.
+    // Mainly for videos that have a 2D and a 3D SBS variant
+    if original_res.is_err()
+        && let Some(parent_path) = build_parent_funscript_path(video_path, base_path, variant)
+        && let Ok(data) = read_funscript(&parent_path).await
+    {
+        original_res = Ok(data);
+    }
+
+    let mut original = original_res?;
+
     if speed == "halfbeat" {
         original.actions = funscript_utils::half_beat_actions(&original.actions);
     } else if speed == "quarterbeat" {
@@ -90,7 +132,7 @@ pub async fn handle_funscript(
     }
 
     let cal_points = device_manager::get_active_calibration_points();
-    let intensity = match generate_intensity(&original, &cal_points) {
+    let intensity = match generate_intensity(&original, &cal_points, params) {
         Ok(data) => Some(data),
         Err(e) => {
             warn!("Could not generate intensity for {}: {}", video_path, e);
@@ -98,23 +140,11 @@ pub async fn handle_funscript(
         }
     };
 
-    let (average, peak) = intensity
-        .as_ref()
-        .map(|i| funscript_utils::calculate_intensity_stats(&i.actions))
-        .unwrap_or((0.0, 0.0));
-
-    let volatility = funscript_utils::calculate_volatility(&original.actions);
-
-    HttpResponse::Ok().json(FunscriptResponse {
-        original: Some(original),
-        intensity,
-        peak,
-        average,
-        volatility
-    })
+    Ok((original, intensity))
 }
 
-fn list_variants(base_path: &str, video_path: &str) -> Vec<String> {
+/// Variant names found next to the funscript of `video_path` (and in its parent directory).
+pub fn list_variants(base_path: &str, video_path: &str) -> Vec<String> {
     let v_path = Path::new(video_path);
     let stem = v_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
     let mut variants = Vec::new();
@@ -126,15 +156,15 @@ fn list_variants(base_path: &str, video_path: &str) -> Vec<String> {
         .to_path_buf();
     variants.extend(collect_variants_from_disk(&primary_dir, stem));
 
-    if let Some(p) = v_path.parent() {
-        if !p.as_os_str().is_empty() {
-            let parent_dir = PathBuf::from(base_path)
-                .join(p)
-                .parent()
-                .unwrap_or(Path::new(base_path))
-                .to_path_buf();
-            variants.extend(collect_variants_from_disk(&parent_dir, stem));
-        }
+    if let Some(p) = v_path.parent()
+        && !p.as_os_str().is_empty()
+    {
+        let parent_dir = PathBuf::from(base_path)
+            .join(p)
+            .parent()
+            .unwrap_or(Path::new(base_path))
+            .to_path_buf();
+        variants.extend(collect_variants_from_disk(&parent_dir, stem));
     }
 
     variants.sort();
@@ -196,22 +226,24 @@ async fn read_funscript(path: &Path) -> Result<FunscriptData, String> {
     let content = fs::read_to_string(path)
         .await
         .map_err(|e| format!("Read error {:?}: {}", path, e))?;
-    serde_json::from_str(&content).map_err(|e| format!("Parse error {:?}: {}", path, e))
+    funscript_utils::parse_funscript(&content).map_err(|e| format!("Parse error {:?}: {}", path, e))
 }
 
-fn generate_intensity(original: &FunscriptData, cal_points: &[(f64, f64)]) -> Result<FunscriptData, String> {
+fn generate_intensity(
+    original: &FunscriptData,
+    cal_points: &[(f64, f64)],
+    params: &IntensityParams,
+) -> Result<FunscriptData, String> {
     if original.actions.len() < 2 {
         return Err("Funscript has fewer than 2 actions".to_string());
     }
 
     let actions = original.actions.clone();
-    let intensity_actions = funscript_utils::actions_to_intensity_curve(&actions, cal_points);
+    let intensity_actions = funscript_utils::actions_to_intensity_curve(&actions, cal_points, params);
 
     if intensity_actions.is_empty() {
         return Err(
-            "Could not generate intensity curve. \
-             The funscript may not be a binary (0/100) script or has insufficient data."
-                .to_string(),
+            "Could not generate intensity curve. The funscript has insufficient data.".to_string(),
         );
     }
 
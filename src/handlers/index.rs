@@ -39,7 +39,7 @@ pub async fn get_directory_tree() -> impl Responder {
 
     let video_base = match required_env_path(VIDEO_SHARE_ENV) {
         Ok(path) => path,
-        Err(response) => return response,
+        Err(response) => return *response,
     };
 
     let mut directory_tree = match directory_browser::build_directory_tree(&video_base, "") {
@@ -178,11 +178,13 @@ fn sort_file_nodes(children: &mut [FileNode]) {
     });
 }
 
-fn required_env_path(key: &str) -> Result<PathBuf, HttpResponse> {
+fn required_env_path(key: &str) -> Result<PathBuf, Box<HttpResponse>> {
     env::var(key).map(PathBuf::from).map_err(|e| {
         error!("{key} not set: {e}");
-        HttpResponse::InternalServerError()
-            .body(format!("Server configuration error: {key} not set"))
+        Box::new(
+            HttpResponse::InternalServerError()
+                .body(format!("Server configuration error: {key} not set")),
+        )
     })
 }
 
@@ -235,23 +237,23 @@ fn apply_cache_fallbacks(cache: &mut serde_json::Map<String, serde_json::Value>,
         }
 
         // Fallback: /Path/To/Video.mp4 -> /Path/Video.funscript (and all variants)
-        if let (Some(parent), Some(stem)) = (rel_video.parent(), rel_video.file_stem()) {
-            if let Some(grandparent) = parent.parent() {
-                let stem_str = stem.to_string_lossy();
-                let fallback_base = grandparent.join(&*stem_str).to_string_lossy().to_string();
-
-                let exact_match = format!("{}.funscript", fallback_base);
-                let variant_prefix = format!("{}.", fallback_base);
-
-                for (cache_key, stats) in cache.iter() {
-                    let is_exact = cache_key == &exact_match;
-                    let is_variant = cache_key.starts_with(&variant_prefix) && cache_key.ends_with(".funscript");
-
-                    if is_exact || is_variant {
-                        let suffix = &cache_key[fallback_base.len()..];
-                        let alias_key = format!("{}{}", video_stem_str, suffix);
-                        aliases.push((alias_key, stats.clone()));
-                    }
+        if let (Some(parent), Some(stem)) = (rel_video.parent(), rel_video.file_stem())
+            && let Some(grandparent) = parent.parent()
+        {
+            let stem_str = stem.to_string_lossy();
+            let fallback_base = grandparent.join(&*stem_str).to_string_lossy().to_string();
+
+            let exact_match = format!("{}.funscript", fallback_base);
+            let variant_prefix = format!("{}.", fallback_base);
+
+            for (cache_key, stats) in cache.iter() {
+                let is_exact = cache_key == &exact_match;
+                let is_variant = cache_key.starts_with(&variant_prefix) && cache_key.ends_with(".funscript");
+
+                if is_exact || is_variant {
+                    let suffix = &cache_key[fallback_base.len()..];
+                    let alias_key = format!("{}{}", video_stem_str, suffix);
+                    aliases.push((alias_key, stats.clone()));
                 }
             }
         }
@@ -0,0 +1,149 @@
+// src/handlers/intensity_presets.rs
+
+//! Intensity parameter preset handler module
+//!
+//! Named `IntensityParams` presets for the intensity curve generation. The built-in
+//! presets (default, responsive, smooth) are always available; presets saved through the
+//! API are stored as a JSON file (.intensity_presets.json) under FUNSCRIPT_SHARE_PATH and
+//! take precedence over a built-in preset of the same name. The funscript and editor
+//! handlers resolve a preset name plus individual overrides per request.
+
+use crate::buttplug::funscript_utils::{self, IntensityParams, IntensityParamsUpdate};
+use actix_web::{HttpResponse, Responder, web};
+use log::{error, info};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    io::ErrorKind,
+    path::PathBuf,
+    str::FromStr,
+};
+use tokio::fs;
+
+pub type IntensityPresets = BTreeMap<String, IntensityParams>;
+
+const PRESETS_FILE_NAME: &str = ".intensity_presets.json";
+
+#[derive(Deserialize)]
+pub struct SavePresetPayload {
+    pub name: String,
+    /// Missing fields take the default values.
+    pub params: IntensityParamsUpdate,
+}
+
+fn preset_store_path() -> PathBuf {
+    let base_dir = env::var("FUNSCRIPT_SHARE_PATH").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(base_dir).join(PRESETS_FILE_NAME)
+}
+
+async fn read_presets_file() -> Result<IntensityPresets, String> {
+    let path = preset_store_path();
+
+    match fs::read_to_string(&path).await {
+        Ok(raw_json) => {
+            serde_json::from_str(&raw_json).map_err(|e| format!("Failed to parse intensity preset JSON: {e}"))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Failed to read intensity preset file {:?}: {e}", path)),
+    }
+}
+
+async fn write_presets_file(presets: &IntensityPresets) -> Result<(), String> {
+    let path = preset_store_path();
+    let json = serde_json::to_string_pretty(presets)
+        .map_err(|e| format!("Failed to serialize intensity presets: {e}"))?;
+
+    if let Some(parent_dir) = path.parent() {
+        fs::create_dir_all(parent_dir)
+            .await
+            .map_err(|e| format!("Failed to create directory {:?}: {e}", parent_dir))?;
+    }
+
+    fs::write(&path, json)
+        .await
+        .map_err(|e| format!("Failed to write intensity preset file {:?}: {e}", path))
+}
+
+/// Built-in presets overlaid with the saved ones. A broken preset file only hides the
+/// saved presets.
+pub async fn all_presets() -> IntensityPresets {
+    let mut presets: IntensityPresets = funscript_utils::builtin_intensity_presets()
+        .into_iter()
+        .map(|(name, params)| (name.to_string(), params))
+        .collect();
+    match read_presets_file().await {
+        Ok(saved) => presets.extend(saved),
+        Err(e) => error!("Failed to load intensity presets: {e}"),
+    }
+    presets
+}
+
+/// Parameters for one request: the named preset (the defaults when `None`) with `update`
+/// applied on top.
+pub async fn resolve_params(preset: Option<&str>, update: IntensityParamsUpdate) -> Result<IntensityParams, String> {
+    let base = match preset.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(name) => all_presets()
+            .await
+            .remove(name)
+            .ok_or_else(|| format!("Unknown intensity preset {name:?}"))?,
+        None => IntensityParams::default(),
+    };
+    base.merged(update)
+}
+
+fn query_value<T: FromStr>(query: &HashMap<String, String>, key: &str) -> Result<Option<T>, String> {
+    query
+        .get(key)
+        .map(|raw| raw.trim().parse().map_err(|_| format!("Invalid {key} value {raw:?}")))
+        .transpose()
+}
+
+/// Individual parameter overrides from query parameters (`step_ms`, `window_ms`,
+/// `max_rise_per_sec`, `smoothing`, `merge_gap_ms`).
+pub fn update_from_query(query: &HashMap<String, String>) -> Result<IntensityParamsUpdate, String> {
+    Ok(IntensityParamsUpdate {
+        step_ms: query_value(query, "step_ms")?,
+        window_ms: query_value(query, "window_ms")?,
+        max_rise_per_sec: query_value(query, "max_rise_per_sec")?,
+        smoothing: query_value(query, "smoothing")?,
+        merge_gap_ms: query_value(query, "merge_gap_ms")?,
+    })
+}
+
+pub async fn get_presets() -> impl Responder {
+    HttpResponse::Ok().json(all_presets().await)
+}
+
+pub async fn save_preset(payload: web::Json<SavePresetPayload>) -> impl Responder {
+    let payload = payload.into_inner();
+    let preset_name = payload.name.trim();
+    if preset_name.is_empty() {
+        return HttpResponse::BadRequest().body("Preset name cannot be empty");
+    }
+    let params = match IntensityParams::default().merged(payload.params) {
+        Ok(params) => params,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    let mut presets = match read_presets_file().await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("Failed to load existing intensity presets before save: {e}");
+            return HttpResponse::InternalServerError().body("Failed to save intensity preset");
+        }
+    };
+
+    presets.insert(preset_name.to_string(), params);
+
+    match write_presets_file(&presets).await {
+        Ok(()) => {
+            info!("Saved intensity preset: {preset_name}");
+            HttpResponse::Ok().json(serde_json::json!({ "ok": true }))
+        }
+        Err(e) => {
+            error!("Failed to save intensity preset {preset_name}: {e}");
+            HttpResponse::InternalServerError().body("Failed to save intensity preset")
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// src/handlers/lint.rs
+
+//! Funscript lint handler module
+//!
+//! `GET /api/funscripts/lint?path=<script>` checks one funscript (a path relative to
+//! FUNSCRIPT_SHARE_PATH; a video path checks its original script) with
+//! `funscript_utils::lint_funscript`, including the check against the video length when
+//! the video is found under VIDEO_SHARE_PATH. Without `path` it lints the whole library
+//! and lists every script with problems; `durations=true` adds the video length check
+//! there too, which runs ffprobe for every script.
+
+use crate::{
+    buttplug::funscript_utils::{self, LintDiagnostic, LintSeverity},
+    handlers::{analysis, editor},
+};
+use actix_web::{HttpResponse, Responder, web};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+use walkdir::WalkDir;
+
+const VIDEO_EXTENSIONS: [&str; 4] = ["mp4", "mkv", "avi", "mov"];
+
+#[derive(Deserialize)]
+pub struct LintQuery {
+    pub path: Option<String>,
+    /// Check the library against the video lengths as well.
+    #[serde(default)]
+    pub durations: bool,
+}
+
+#[derive(Serialize)]
+pub struct ScriptLint {
+    pub path: String,
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<LintDiagnostic>,
+}
+
+#[derive(Serialize)]
+pub struct LintReport {
+    /// Number of scripts checked.
+    pub checked: usize,
+    /// Scripts with at least one diagnostic, the most errors first.
+    pub scripts: Vec<ScriptLint>,
+}
+
+impl ScriptLint {
+    fn new(path: String, diagnostics: Vec<LintDiagnostic>) -> Self {
+        let count = |severity| diagnostics.iter().filter(|d| d.severity == severity).count();
+        Self {
+            path,
+            errors: count(LintSeverity::Error),
+            warnings: count(LintSeverity::Warning),
+            diagnostics,
+        }
+    }
+}
+
+/// Video for the script at `funscript_rel`: same path and stem, or without the variant
+/// suffix for variant scripts.
+fn find_video(video_dir: &Path, funscript_rel: &Path) -> Option<PathBuf> {
+    let base = video_dir.join(funscript_rel.with_extension(""));
+    let mut bases = vec![base.clone()];
+    if base.extension().is_some() {
+        bases.push(base.with_extension(""));
+    }
+
+    bases
+        .iter()
+        .flat_map(|base| {
+            VIDEO_EXTENSIONS.iter().map(move |ext| {
+                let mut path = OsString::from(base.as_os_str());
+                path.push(".");
+                path.push(ext);
+                PathBuf::from(path)
+            })
+        })
+        .find(|path| path.is_file())
+}
+
+async fn video_duration_ms(funscript_rel: &Path) -> Option<u64> {
+    let video_dir = PathBuf::from(env::var("VIDEO_SHARE_PATH").ok()?);
+    let video = find_video(&video_dir, funscript_rel)?;
+    analysis::get_video_duration_ffprobe(&video).await.map(|secs| secs * 1000)
+}
+
+async fn lint_file(funscript_dir: &Path, rel: &Path, check_video: bool) -> Result<ScriptLint, String> {
+    let path = funscript_dir.join(rel);
+    let content = fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let video_ms = if check_video { video_duration_ms(rel).await } else { None };
+
+    let diagnostics = funscript_utils::lint_funscript(&content, video_ms);
+    Ok(ScriptLint::new(rel.to_string_lossy().to_string(), diagnostics))
+}
+
+pub async fn lint_funscripts(query: web::Query<LintQuery>) -> impl Responder {
+    let funscript_dir = match env::var("FUNSCRIPT_SHARE_PATH") {
+        Ok(f) => PathBuf::from(f),
+        Err(_) => return HttpResponse::InternalServerError().body("FUNSCRIPT_SHARE_PATH not set"),
+    };
+
+    if let Some(path) = &query.path {
+        let mut rel = PathBuf::from(path);
+        if !editor::is_safe_relative_path(&rel) {
+            return HttpResponse::BadRequest().body("Invalid path");
+        }
+        if rel.extension().and_then(|e| e.to_str()) != Some("funscript") {
+            rel.set_extension("funscript");
+        }
+        if !funscript_dir.join(&rel).is_file() {
+            return HttpResponse::NotFound().body("Funscript not found");
+        }
+        return match lint_file(&funscript_dir, &rel, true).await {
+            Ok(lint) => HttpResponse::Ok().json(lint),
+            Err(e) => {
+                error!("{e}");
+                HttpResponse::InternalServerError().body("Failed to read funscript")
+            }
+        };
+    }
+
+    let dir = funscript_dir.clone();
+    let scripts = tokio::task::spawn_blocking(move || {
+        WalkDir::new(&dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| {
+                e.path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("funscript"))
+            })
+            .filter_map(|e| e.path().strip_prefix(&dir).ok().map(Path::to_path_buf))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    let mut report = LintReport {
+        checked: scripts.len(),
+        scripts: Vec::new(),
+    };
+    for rel in &scripts {
+        match lint_file(&funscript_dir, rel, query.durations).await {
+            Ok(lint) if !lint.diagnostics.is_empty() => report.scripts.push(lint),
+            Ok(_) => {}
+            Err(e) => error!("{e}"),
+        }
+    }
+    report.scripts.sort_by(|a, b| {
+        (b.errors, b.warnings)
+            .cmp(&(a.errors, a.warnings))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    HttpResponse::Ok().json(report)
+}
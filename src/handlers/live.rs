@@ -0,0 +1,80 @@
+// src/handlers/live.rs
+
+//! Live output stream module
+//!
+//! `GET /api/live` is a Server-Sent Events stream for stream overlays and secondary
+//! displays. At a fixed rate it publishes what the devices last received (after limits,
+//! curves and calibration), the script position and the upcoming stroke hits, so a page
+//! can visualise the session without opening a control WebSocket.
+
+use crate::buttplug::device_manager::{self, AppliedActuator, AppliedOutput};
+use actix_web::{HttpResponse, Responder, web::Bytes};
+use buttplug::core::message::ActuatorType;
+use futures::stream;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::time::{self, MissedTickBehavior};
+
+/// Delay between two events.
+const LIVE_INTERVAL: Duration = Duration::from_millis(100);
+/// How far ahead upcoming beats are listed.
+const BEAT_HORIZON_MS: f64 = 5000.0;
+
+#[derive(Serialize)]
+pub struct LiveFrame {
+    pub position_ms: f64,
+    pub playing: bool,
+    pub stopped: bool,
+    /// Highest value sent to any oscillating actuator (0.0 .. 1.0).
+    pub oscillate: f64,
+    /// Highest value sent to any vibrator (0.0 .. 1.0).
+    pub vibrate: f64,
+    /// Script times (ms) of the stroke hits in the next few seconds.
+    pub beats: Vec<u64>,
+    pub actuators: Vec<AppliedActuator>,
+}
+
+fn peak(output: &AppliedOutput, actuator_type: ActuatorType) -> f64 {
+    output
+        .actuators
+        .iter()
+        .filter(|a| a.actuator_type == actuator_type)
+        .map(|a| a.value)
+        .fold(0.0, f64::max)
+}
+
+fn live_frame(output: &AppliedOutput) -> LiveFrame {
+    LiveFrame {
+        position_ms: output.position_ms,
+        playing: output.playing,
+        stopped: output.stopped,
+        oscillate: peak(output, ActuatorType::Oscillate),
+        vibrate: peak(output, ActuatorType::Vibrate),
+        beats: device_manager::upcoming_beats(output.position_ms, BEAT_HORIZON_MS),
+        actuators: output.actuators.clone(),
+    }
+}
+
+pub async fn handle_live() -> impl Responder {
+    let Some(output) = device_manager::subscribe_applied_output() else {
+        return HttpResponse::ServiceUnavailable().body("Device manager is not running");
+    };
+
+    let mut interval = time::interval(LIVE_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let events = stream::unfold((output, interval), |(output, mut interval)| async move {
+        interval.tick().await;
+        let frame = live_frame(&output.borrow());
+        let json = serde_json::to_string(&frame).ok()?;
+        let event = Bytes::from(format!("data: {json}\n\n"));
+        Some((Ok::<_, actix_web::Error>(event), (output, interval)))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        // Keep reverse proxies from buffering the stream
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(events)
+}
@@ -236,12 +236,12 @@ fn get_videos_in_folder(tree: &FileNode, folder_path: &str) -> Vec<FileNode> {
     };
 
     let mut result = Vec::new();
-    if let Some(node) = target_node {
-        if let Some(ref children) = node.children {
-            for child in children {
-                if !child.is_dir {
-                    result.push(child.clone());
-                }
+    if let Some(node) = target_node
+        && let Some(ref children) = node.children
+    {
+        for child in children {
+            if !child.is_dir {
+                result.push(child.clone());
             }
         }
     }
@@ -0,0 +1,131 @@
+// src/handlers/remote.rs
+
+//! Remote control handler module
+//!
+//! Serves the phone remote page and its API: `GET /api/remote` describes the active
+//! playback session and `POST /api/remote` runs one `RemoteCommand` against it (see
+//! `crate::remote` for how commands reach the player).
+
+use crate::{
+    buttplug::device_manager,
+    control_lease,
+    handlers::funscript,
+    remote::{self, ActiveScript, RemoteCommand, SPEED_MODES},
+};
+use actix_files::NamedFile;
+use actix_web::{Error, HttpResponse, Responder, web};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::env;
+
+const REMOTE_PAGE_PATH: &str = "./static/remote.html";
+
+#[derive(Serialize)]
+pub struct RemoteState {
+    pub active: Option<ActiveScript>,
+    /// Variants available for the active script.
+    pub variants: Vec<String>,
+    pub speeds: &'static [&'static str],
+    pub playing: bool,
+    pub position_ms: Option<f64>,
+    pub stopped: bool,
+    /// Global max limit in percent.
+    pub max_limit: f64,
+    /// Address of the player in control, if any.
+    pub player: Option<String>,
+}
+
+pub async fn handle_remote_page() -> Result<impl Responder, Error> {
+    Ok(NamedFile::open(REMOTE_PAGE_PATH)?
+        .customize()
+        .insert_header(("Cache-Control", "no-cache")))
+}
+
+fn active_variants(active: Option<&ActiveScript>) -> Vec<String> {
+    let (Some(active), Ok(base_path)) = (active, env::var("FUNSCRIPT_SHARE_PATH")) else {
+        return Vec::new();
+    };
+    let variants = funscript::list_variants(&base_path, &active.path);
+    if variants.is_empty() {
+        vec!["original".to_string()]
+    } else {
+        variants
+    }
+}
+
+pub async fn get_remote_state() -> impl Responder {
+    let active = remote::active();
+    let status = device_manager::playback_status();
+    HttpResponse::Ok().json(RemoteState {
+        variants: active_variants(active.as_ref()),
+        active,
+        speeds: SPEED_MODES,
+        playing: status.as_ref().is_some_and(|s| s.playing),
+        position_ms: status.map(|s| s.position_ms),
+        stopped: device_manager::is_stopped(),
+        max_limit: device_manager::get_max_limit() * 100.0,
+        player: control_lease::state().controller_addr,
+    })
+}
+
+fn validate(command: &RemoteCommand) -> Result<(), String> {
+    match command {
+        RemoteCommand::Resume if device_manager::is_stopped() => {
+            Err("Emergency stop is active; re-arm before resuming".to_string())
+        }
+        RemoteCommand::SetSpeed { speed } if !SPEED_MODES.contains(&speed.as_str()) => {
+            Err(format!("Unknown speed mode {speed:?}"))
+        }
+        RemoteCommand::SetVariant { variant } => {
+            let active = remote::active().ok_or("No script is loaded")?;
+            if active_variants(Some(&active)).contains(variant) {
+                Ok(())
+            } else {
+                Err(format!("Unknown variant {variant:?}"))
+            }
+        }
+        RemoteCommand::SetMaxLimit { limit } if !(0.0..=100.0).contains(limit) => {
+            Err("limit must be between 0 and 100".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn post_remote_command(payload: web::Json<RemoteCommand>) -> impl Responder {
+    let command = payload.into_inner();
+    if let Err(e) = validate(&command) {
+        warn!("Rejected remote command {command:?}: {e}");
+        return HttpResponse::BadRequest().body(e);
+    }
+    info!("Remote command: {command:?}");
+
+    let result = match command {
+        RemoteCommand::Stop => {
+            if let Err(e) = device_manager::emergency_stop().await {
+                error!("Emergency stop could not reach Intiface: {e}");
+                return HttpResponse::BadGateway().body(e);
+            }
+            Ok(())
+        }
+        RemoteCommand::Rearm => {
+            device_manager::rearm();
+            Ok(())
+        }
+        RemoteCommand::SetMaxLimit { limit } => {
+            device_manager::set_max_limit(limit / 100.0);
+            Ok(())
+        }
+        RemoteCommand::Pause => {
+            device_manager::pause_here();
+            // The devices are paused either way; the player may just not be around
+            let _ = remote::relay(RemoteCommand::Pause);
+            Ok(())
+        }
+        command => remote::relay(command),
+    };
+
+    match result {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "ok": true })),
+        Err(e) => HttpResponse::Conflict().body(e),
+    }
+}
@@ -0,0 +1,122 @@
+// src/handlers/sessions.rs
+
+//! Session recording handler module
+//!
+//! Starts and stops recordings of the applied device output, lists and serves the saved
+//! session files and replays them through the device layer. Recording and replay
+//! themselves live in `crate::buttplug::session_recording`.
+
+use crate::{
+    buttplug::{
+        device_manager::{self, ReplayStatus},
+        session_recording::{self, SessionMeta},
+    },
+    remote,
+};
+use actix_web::{HttpResponse, Responder, web};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize)]
+pub struct SessionsState {
+    /// Samples recorded so far, while a recording runs.
+    pub recording: Option<usize>,
+    pub replay: Option<ReplayStatus>,
+    /// Saved sessions, newest first.
+    pub sessions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReplayPayload {
+    /// Where to start in the recording, in ms.
+    #[serde(default)]
+    pub from_ms: u64,
+}
+
+pub async fn get_sessions() -> impl Responder {
+    match session_recording::list_session_files().await {
+        Ok(sessions) => HttpResponse::Ok().json(SessionsState {
+            recording: device_manager::recording_samples(),
+            replay: device_manager::replay_status(),
+            sessions,
+        }),
+        Err(e) => {
+            error!("Failed to list sessions: {e}");
+            HttpResponse::InternalServerError().body("Failed to list sessions")
+        }
+    }
+}
+
+pub async fn start_recording() -> impl Responder {
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let active = remote::active();
+    let meta = SessionMeta {
+        started_at,
+        video: active.as_ref().map(|a| a.path.clone()),
+        variant: active.as_ref().map(|a| a.variant.clone()),
+        speed: active.map(|a| a.speed),
+        ..SessionMeta::default()
+    };
+
+    match device_manager::start_recording(meta).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "ok": true })),
+        Err(e) => HttpResponse::Conflict().body(e),
+    }
+}
+
+pub async fn stop_recording() -> impl Responder {
+    let Some(recording) = device_manager::stop_recording() else {
+        return HttpResponse::Conflict().body("No recording is running");
+    };
+
+    let name = format!("session-{}", recording.meta.started_at);
+    match session_recording::write_session_file(&name, &recording).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "ok": true,
+            "name": name,
+            "samples": recording.samples.len(),
+            "duration_ms": recording.duration_ms(),
+        })),
+        Err(e) => {
+            error!("Failed to save session {name}: {e}");
+            HttpResponse::InternalServerError().body("Failed to save session")
+        }
+    }
+}
+
+pub async fn get_session(path: web::Path<String>) -> impl Responder {
+    match session_recording::read_session_file(&path.into_inner()).await {
+        Ok(recording) => HttpResponse::Ok().json(recording),
+        Err(e) => {
+            warn!("{e}");
+            HttpResponse::NotFound().body("Session not found")
+        }
+    }
+}
+
+pub async fn start_replay(path: web::Path<String>, payload: web::Json<ReplayPayload>) -> impl Responder {
+    let name = path.into_inner();
+    let recording = match session_recording::read_session_file(&name).await {
+        Ok(recording) => recording,
+        Err(e) => {
+            warn!("{e}");
+            return HttpResponse::NotFound().body("Session not found");
+        }
+    };
+    if payload.from_ms >= recording.duration_ms() {
+        return HttpResponse::BadRequest().body("from_ms is past the end of the session");
+    }
+
+    match device_manager::start_replay(name, recording, payload.from_ms).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "ok": true })),
+        Err(e) => HttpResponse::Conflict().body(e),
+    }
+}
+
+pub async fn stop_replay() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({ "ok": true, "stopped": device_manager::stop_replay() }))
+}
@@ -37,7 +37,7 @@ pub async fn handle_thumbnail(
     }
 
     if let Some(parent) = thumb_full_path.parent() {
-        fs::create_dir_all(parent).await.map_err(|e| ErrorInternalServerError(e))?;
+        fs::create_dir_all(parent).await.map_err(ErrorInternalServerError)?;
     }
     let output = tokio::process::Command::new("ffmpeg")
         .args([
@@ -1,6 +1,28 @@
 // src/intiface_socket.rs
 
 //! WebSocket handler for device control via the Buttplug protocol.
+//!
+//! Clients do not push intensity values. They load a funscript into the server-side
+//! playback engine and then only report transport events (play, pause, seek) and
+//! periodic timestamp syncs; the device control loop derives the output itself.
+//! A `manual` message lets the calibration page drive the oscillator directly.
+//! `{"stop": true}` triggers the latched emergency stop; `{"type": "rearm"}` releases it.
+//! The message schema and its versions live in `ws_protocol`.
+//!
+//! Every valid message feeds the device manager's watchdog. When the watchdog stopped
+//! the devices because all clients went silent, newly connecting clients are told: in
+//! the `welcome` for version 2 clients, otherwise with a `watchdog_stop` message once the
+//! handshake grace period has passed.
+//!
+//! Device events (connection changes, devices coming and going, battery/RSSI readings and
+//! low-battery warnings) are pushed to every client; version 2 clients also get stop
+//! state and max limit changes, and `intensity` frames when they ask for them.
+//!
+//! Each connection is registered with `control_lease`. Only the controller may drive the
+//! devices; the others are observers whose driving commands are rejected (the emergency
+//! stop always goes through) until they send `take_control`. Observers do not feed the
+//! watchdog, so a silent controller still stops the devices. The controller also receives
+//! the pause, resume, speed and variant commands of the remote page.
 
 
 
@@ -10,21 +32,263 @@
 
 
 
-use crate::buttplug::device_manager;
+use crate::buttplug::{device_manager, funscript_utils::IntensityParamsUpdate};
+use crate::control_lease::{self, SessionId};
+use crate::handlers::{funscript, intensity_presets};
+use crate::remote;
+use crate::ws_protocol::{
+    CAP_INTENSITY, ClientMessage, ControlState, LEGACY_VERSION, PROTOCOL_VERSION, SERVER_CAPABILITIES,
+    ServerMessage, StateSnapshot, parse_client_message,
+};
 
 use actix_web::{Error, HttpRequest, HttpResponse, web};
-use actix_ws::Message;
+use actix_ws::{Message, Session};
 use futures::StreamExt;
-use log::{debug, error, info};
-use serde::Deserialize;
+use log::{debug, error, info, warn};
+use std::{
+    env,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+/// How long a new client has to send `hello` before it is treated as version 1.
+const HELLO_GRACE: Duration = Duration::from_secs(1);
+/// Minimum delay between two `intensity` frames to one client.
+const INTENSITY_PUSH_INTERVAL: Duration = Duration::from_millis(200);
+
+
+
+
+async fn load_playback(
+    path: &str,
+    variant: Option<&str>,
+    speed: Option<&str>,
+    preset: Option<&str>,
+) -> Result<(), String> {
+    let base_path = env::var("FUNSCRIPT_SHARE_PATH")
+        .map_err(|e| format!("FUNSCRIPT_SHARE_PATH not set ({e})"))?;
+    let params = intensity_presets::resolve_params(preset, IntensityParamsUpdate::default()).await?;
+    let (original, intensity) = funscript::load_funscript(
+        &base_path,
+        path,
+        variant.unwrap_or("original"),
+        speed.unwrap_or("normal"),
+        &params,
+    )
+    .await?;
+
+    let intensity = intensity.map(|i| i.actions).unwrap_or_default();
+    device_manager::load_playback(path.to_string(), original.actions, intensity);
+    remote::set_active(path, variant, speed);
+    Ok(())
+}
 
+async fn handle_message(msg: ClientMessage) -> Result<(), String> {
+    if device_manager::is_stopped()
+        && matches!(msg, ClientMessage::Play { .. } | ClientMessage::Manual { o: Some(_) })
+    {
+        return Err("Emergency stop is active; re-arm before resuming".to_string());
+    }
+
+    match msg {
+        ClientMessage::Load {
+            path,
+            variant,
+            speed,
+            preset,
+        } => load_playback(&path, variant.as_deref(), speed.as_deref(), preset.as_deref()).await?,
+        ClientMessage::Play { at } => device_manager::play(at),
+        ClientMessage::Pause { at } => device_manager::pause(at),
+        ClientMessage::Seek { at } => device_manager::seek(at),
+        ClientMessage::Sync { at } => device_manager::sync_position(at),
+        ClientMessage::Manual { o } => device_manager::set_manual_oscillate(o),
+        ClientMessage::Stop => device_manager::emergency_stop().await?,
+        ClientMessage::Rearm => device_manager::rearm(),
+        ClientMessage::SetMaxLimit(payload) => {
+            payload.apply().await?;
+        }
+        ClientMessage::UpdateDevice { index, settings } => {
+            device_manager::update_device_settings(index, (*settings).into_update()).await?;
+        }
+        // Handled by the connection loop before dispatching
+        ClientMessage::Hello { .. } | ClientMessage::TakeControl | ClientMessage::ReleaseControl => {}
+    }
+    Ok(())
+}
 
+fn control_state(session: SessionId) -> ControlState {
+    ControlState {
+        session,
+        role: control_lease::role(session),
+        lease: control_lease::state(),
+    }
+}
 
+fn state_snapshot(session: SessionId) -> StateSnapshot {
+    StateSnapshot {
+        stopped: device_manager::is_stopped(),
+        max_limit: device_manager::get_max_limit() * 100.0,
+        connection: device_manager::get_connection_state(),
+        watchdog_stop: device_manager::watchdog_stop_position(),
+        control: control_state(session),
+    }
+}
 
-#[derive(Deserialize)]
-struct ControlCommand {
-    o: Option<f64>,
-    v: Option<f64>,
+/// Send `msg` in the form the client's protocol version understands. Returns false once
+/// the connection is closed.
+async fn send(session: &mut Session, version: &AtomicU32, msg: ServerMessage) -> bool {
+    match msg.encode(version.load(Ordering::SeqCst)) {
+        Some(frame) => session.text(frame).await.is_ok(),
+        None => true,
+    }
+}
+
+/// Push device events, stop state and limit changes until the connection closes.
+async fn forward_events(mut session: Session, version: Arc<AtomicU32>) {
+    let Some(mut events) = device_manager::subscribe_events() else {
+        return;
+    };
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("WebSocket client missed {} device events", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if !send(&mut session, &version, event.into()).await {
+            break;
+        }
+    }
+}
+
+/// Tell the client whenever the control lease changes hands, until the connection closes.
+async fn forward_lease(mut session: Session, version: Arc<AtomicU32>, id: SessionId) {
+    let mut changes = control_lease::subscribe();
+    loop {
+        let lease = match changes.recv().await {
+            Ok(lease) => lease,
+            Err(RecvError::Lagged(_)) => control_lease::state(),
+            Err(RecvError::Closed) => break,
+        };
+        let role = if lease.controller == Some(id) {
+            control_lease::Role::Controller
+        } else {
+            control_lease::Role::Observer
+        };
+        let msg = ServerMessage::Control(ControlState {
+            session: id,
+            role,
+            lease,
+        });
+        if !send(&mut session, &version, msg).await {
+            break;
+        }
+    }
+}
+
+/// Pass remote page commands on while this session is in control.
+async fn forward_remote(mut session: Session, version: Arc<AtomicU32>, id: SessionId) {
+    let mut commands = remote::subscribe();
+    loop {
+        let command = match commands.recv().await {
+            Ok(command) => command,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("WebSocket client missed {} remote commands", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if control_lease::role(id) != control_lease::Role::Controller {
+            continue;
+        }
+        if !send(&mut session, &version, ServerMessage::Remote { command }).await {
+            break;
+        }
+    }
+}
+
+/// Run a command from session `id`, claiming the control lease first if it needs it.
+async fn dispatch(id: SessionId, msg: ClientMessage) -> Result<(), String> {
+    match msg {
+        ClientMessage::TakeControl => {
+            if control_lease::take_over(id) {
+                info!("WebSocket session {} took control", id);
+            }
+            Ok(())
+        }
+        ClientMessage::ReleaseControl => {
+            control_lease::release(id);
+            Ok(())
+        }
+        msg => {
+            if msg.needs_control() {
+                control_lease::acquire(id)?;
+            }
+            device_manager::touch_watchdog();
+            handle_message(msg).await
+        }
+    }
+}
+
+/// Push the applied device output, at most every `INTENSITY_PUSH_INTERVAL`.
+async fn forward_intensity(mut session: Session) {
+    let Some(mut output) = device_manager::subscribe_applied_output() else {
+        return;
+    };
+    while output.changed().await.is_ok() {
+        let frame = ServerMessage::Intensity(output.borrow_and_update().clone());
+        if let Some(text) = frame.encode(PROTOCOL_VERSION)
+            && session.text(text).await.is_err()
+        {
+            break;
+        }
+        tokio::time::sleep(INTENSITY_PUSH_INTERVAL).await;
+    }
+}
+
+/// Tell version 1 clients that the watchdog stopped the devices, once the grace period
+/// for a `hello` has passed.
+async fn notify_legacy_watchdog_stop(mut session: Session, version: Arc<AtomicU32>) {
+    tokio::time::sleep(HELLO_GRACE).await;
+    if version.load(Ordering::SeqCst) != LEGACY_VERSION {
+        return;
+    }
+    if let Some(at) = device_manager::watchdog_stop_position() {
+        send(&mut session, &version, ServerMessage::WatchdogStop { at }).await;
+    }
+}
+
+/// Negotiate the protocol version and greet version 2 clients with the current state.
+async fn handle_hello(
+    session: &mut Session,
+    version: &Arc<AtomicU32>,
+    id: SessionId,
+    requested: u32,
+    capabilities: &[String],
+) {
+    let negotiated = requested.clamp(LEGACY_VERSION, PROTOCOL_VERSION);
+    version.store(negotiated, Ordering::SeqCst);
+    info!("WebSocket client speaks protocol version {negotiated} ({capabilities:?})");
+    if negotiated < 2 {
+        return;
+    }
+
+    let welcome = ServerMessage::Welcome {
+        version: negotiated,
+        capabilities: SERVER_CAPABILITIES,
+        state: state_snapshot(id),
+    };
+    send(session, version, welcome).await;
+
+    if capabilities.iter().any(|c| c == CAP_INTENSITY) {
+        actix_web::rt::spawn(forward_intensity(session.clone()));
+    }
 }
 
 
@@ -102,28 +366,34 @@ pub async fn handle_ws_start(
     let (response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
     info!("WebSocket connection established with {}", addr);
 
+    let id = control_lease::register(&addr);
+    let version = Arc::new(AtomicU32::new(LEGACY_VERSION));
+    actix_web::rt::spawn(forward_events(session.clone(), version.clone()));
+    actix_web::rt::spawn(forward_lease(session.clone(), version.clone(), id));
+    actix_web::rt::spawn(forward_remote(session.clone(), version.clone(), id));
+    actix_web::rt::spawn(notify_legacy_watchdog_stop(session.clone(), version.clone()));
+
     actix_web::rt::spawn(async move {
         while let Some(Ok(msg)) = msg_stream.next().await {
             match msg {
-                Message::Text(text) => match serde_json::from_str::<ControlCommand>(&text) {
+                Message::Text(text) => match parse_client_message(&text) {
+                    Ok(ClientMessage::Hello {
+                        version: requested,
+                        capabilities,
+                    }) => {
+                        device_manager::touch_watchdog();
+                        handle_hello(&mut session, &version, id, requested, &capabilities).await;
+                    }
                     Ok(cmd) => {
-                        if let Some(o) = cmd.o {
-                            let clamped = o.max(0.0).min(1.0);
-                            device_manager::set_oscillate(clamped);
-                        }
-                        if let Some(v) = cmd.v {
-                            let clamped = v.max(0.0).min(1.0);
-                            device_manager::set_vibrate(clamped);
+                        if let Err(e) = dispatch(id, cmd).await {
+                            warn!("WebSocket command failed: {}", e);
+                            send(&mut session, &version, ServerMessage::Error { message: e }).await;
                         }
                     }
                     Err(e) => {
                         error!("Invalid JSON command: {}", e);
-                        let _ = session
-                            .text(
-                                serde_json::json!({ "error": format!("invalid JSON: {}", e) })
-                                    .to_string(),
-                            )
-                            .await;
+                        let message = format!("invalid JSON: {}", e);
+                        send(&mut session, &version, ServerMessage::Error { message }).await;
                     }
                 },
                 Message::Ping(bytes) => {
@@ -139,16 +409,13 @@ pub async fn handle_ws_start(
                 }
                 Message::Binary(bin) => {
                     error!("Unexpected binary message of {} bytes", bin.len());
-                    let _ = session
-                        .text(
-                            serde_json::json!({ "error": "binary messages not supported" })
-                                .to_string(),
-                        )
-                        .await;
+                    let message = "binary messages not supported".to_string();
+                    send(&mut session, &version, ServerMessage::Error { message }).await;
                 }
                 _ => {}
             }
         }
+        control_lease::unregister(id);
         info!("WebSocket connection closed for {}", addr);
     });
 
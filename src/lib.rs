@@ -15,34 +15,65 @@
 //! The application is split into several modules:
 //!
 //! - `routes`: HTTP routing configuration
+//! - `auth`: Optional login and allowed origins
 //! - `handlers`: Request handlers for videos and funscripts
 //! - `directory_browser`: File system navigation
 //! - `intiface_socket`: WebSocket handler for device communication
+//! - `ws_protocol`: Versioned message schema of the WebSocket
+//! - `control_lease`: Which WebSocket session may drive the devices
+//! - `remote`: Remote control of the active playback session
 //! - `buttplug`: Device control and funscript processing
 
 pub mod routes;
 pub mod handlers {
     pub mod analysis;
+    pub mod auth;
     pub mod calibration;
+    pub mod devices;
     pub mod editor;
     pub mod funscript;
     pub mod index;
+    pub mod intensity_presets;
+    pub mod lint;
+    pub mod live;
     pub mod recommendations;
+    pub mod remote;
+    pub mod sessions;
     pub mod thumbnail;
     pub mod video;
 }
 
+pub mod auth;
+pub mod control_lease;
 pub mod directory_browser;
 pub mod funscript_cache;
 pub mod intiface_socket;
+pub mod remote;
+pub mod ws_protocol;
 
 /// Buttplug-related functionality for device control and funscript processing
 ///
 /// The buttplug::device_manager module manages the Buttplug client lifecycle,
 /// device discovery and the periodic control loop. It exposes initialize()
-/// for startup and synchronous control helpers used by the WebSocket actor (for
-/// example: set_oscillate and set_vibrate).
+/// for startup and synchronous playback helpers used by the WebSocket actor (for
+/// example: load_playback, play and pause). The buttplug::playback module holds
+/// the server-side intensity timeline the control loop samples from, and
+/// buttplug::device_settings persists the actuator role assignments.
+/// buttplug::intiface_config holds the server endpoint and loop timings, and
+/// buttplug::output lets the control loop drive either Intiface devices or the
+/// simulated buttplug::virtual_device. buttplug::session_recording records the applied
+/// output into session files and replays them, and buttplug::output_sink fans it out
+/// to OSC (buttplug::osc_sink) and MQTT (buttplug::mqtt_sink).
 pub mod buttplug {
     pub mod device_manager;
+    pub mod device_settings;
     pub mod funscript_utils;
+    pub mod intiface_config;
+    pub mod mqtt_sink;
+    pub mod osc_sink;
+    pub mod output;
+    pub mod output_sink;
+    pub mod playback;
+    pub mod session_recording;
+    pub mod virtual_device;
 }
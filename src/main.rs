@@ -2,18 +2,22 @@
 
 //! Main entry point for the Video Player web server.
 //!
-//! Loads .env and initializes logging. Spawns background tasks for:
+//! Loads .env, initializes logging and loads the saved BPM-to-intensity table. Spawns
+//! background tasks for:
 //! - funscript cache initialization when FUNSCRIPT_SHARE_PATH is set
 //! - Intiface initialization via buttplug::device_manager::initialize()
 //!
-//! Configures Actix HTTP server with logging and permissive CORS. Bind address is
-//! controlled by HOST_IP and SERVER_PORT environment variables
+//! Configures Actix HTTP server with logging, CORS for ALLOWED_ORIGINS and the optional
+//! login (see the auth module). Bind address is controlled by HOST_IP and SERVER_PORT
+//! environment variables
 
-use actix_cors::Cors;
-use actix_web::{App, HttpServer, middleware::Logger};
+use actix_web::{
+    App, HttpServer,
+    middleware::{Logger, from_fn},
+};
 use env_logger::Env;
-use hismith_player_site::{buttplug::device_manager, routes};
-use log::{error, info};
+use hismith_player_site::{auth, buttplug::device_manager, handlers::calibration, routes};
+use log::{error, info, warn};
 use std::env;
 
 #[actix_web::main]
@@ -21,6 +25,8 @@ async fn main() -> std::io::Result<()> {
     dotenvy::dotenv().ok();
     env_logger::init_from_env(Env::default().default_filter_or("info"));
 
+    calibration::load_bpm_table().await;
+
     info!("Starting background cache");
     if let Ok(f) = env::var("FUNSCRIPT_SHARE_PATH") {
         let base = std::path::PathBuf::from(f);
@@ -48,17 +54,19 @@ async fn main() -> std::io::Result<()> {
         .parse::<u16>()
         .expect("SERVER_PORT must be a valid port number (0-65535)");
 
+    let auth_config = auth::config();
+    if auth_config.enabled() {
+        info!("Login required ({} personal tokens)", auth_config.tokens.len());
+    } else {
+        warn!("AUTH_SECRET and AUTH_TOKENS are not set; anyone on the network can control the devices");
+    }
+
     info!("Starting HTTP server on {}:{}...", host_ip, port);
     HttpServer::new(move || {
         App::new()
+            .wrap(from_fn(auth::require_login))
+            .wrap(auth_config.cors())
             .wrap(Logger::default())
-            .wrap(
-                Cors::default()
-                    .allow_any_origin()
-                    .allowed_methods(vec!["GET", "POST", "OPTIONS"])
-                    .allow_any_header()
-                    .max_age(3600),
-            )
             .configure(routes::setup_routes)
     })
     .bind(format!("{}:{}", host_ip, port))?
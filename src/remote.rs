@@ -0,0 +1,71 @@
+// src/remote.rs
+
+//! Remote control of the active playback session
+//!
+//! A second device (typically a phone next to a TV) can steer whatever the player is
+//! showing. Commands that only touch server state (emergency stop, re-arm, max limit) are
+//! applied directly. Pause, resume, speed and variant changes belong to the player page,
+//! which owns the video, so they are relayed to the session holding the control lease
+//! and the player reports the result back like any user action would. Pause is also
+//! applied to the playback engine right away so the devices stop even if the player is
+//! slow to react.
+
+use crate::control_lease;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Speed modes the player understands, as sent in `load` messages.
+pub const SPEED_MODES: &[&str] = &["quarterbeat", "halfbeat", "normal", "doublebeat"];
+
+/// The funscript the player last loaded.
+#[derive(Serialize, Debug, Clone)]
+pub struct ActiveScript {
+    pub path: String,
+    pub variant: String,
+    pub speed: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    Pause,
+    Resume,
+    SetSpeed { speed: String },
+    SetVariant { variant: String },
+    /// Global max limit in percent.
+    SetMaxLimit { limit: f64 },
+    Stop,
+    Rearm,
+}
+
+static ACTIVE: Lazy<Mutex<Option<ActiveScript>>> = Lazy::new(|| Mutex::new(None));
+static RELAY: Lazy<broadcast::Sender<RemoteCommand>> = Lazy::new(|| broadcast::channel(16).0);
+
+/// Record a `load` from the player.
+pub fn set_active(path: &str, variant: Option<&str>, speed: Option<&str>) {
+    *ACTIVE.lock().unwrap() = Some(ActiveScript {
+        path: path.to_string(),
+        variant: variant.unwrap_or("original").to_string(),
+        speed: speed.unwrap_or("normal").to_string(),
+    });
+}
+
+pub fn active() -> Option<ActiveScript> {
+    ACTIVE.lock().unwrap().clone()
+}
+
+/// Hand a player command to the session in control.
+pub fn relay(command: RemoteCommand) -> Result<(), String> {
+    if control_lease::state().controller.is_none() {
+        return Err("No player is connected".to_string());
+    }
+    let _ = RELAY.send(command);
+    Ok(())
+}
+
+/// Commands relayed to the player; each session forwards them only while it is in control.
+pub fn subscribe() -> broadcast::Receiver<RemoteCommand> {
+    RELAY.subscribe()
+}
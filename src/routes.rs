@@ -4,12 +4,16 @@
 //!
 //! Registers endpoints used by the frontend and API:
 //! - /ws -> WebSocket handshake to intiface_socket::handle_ws_start
-//! - /api/* -> REST API endpoints (directory-tree, funscripts, calibration)
+//! - /api/* -> REST API endpoints (auth, directory-tree, funscripts, calibration, intensity presets,
+//!   devices, remote, live, sessions)
 //! - /site/* -> UI pages and static assets; /site/static serves files from ./static
 //!   with a Cache-Control: no-cache header applied.
 
 use crate::{
-    handlers::{analysis, calibration, editor, funscript, index, recommendations, thumbnail, video},
+    handlers::{
+        analysis, auth, calibration, devices, editor, funscript, index, intensity_presets, lint, live,
+        recommendations, remote, sessions, thumbnail, video,
+    },
     intiface_socket,
 };
 use actix_files::Files;
@@ -19,23 +23,54 @@ pub fn setup_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/ws").route(web::get().to(intiface_socket::handle_ws_start)))
         .service(
             web::scope("/api")
+                .route("/auth/status", web::get().to(auth::get_status))
+                .route("/auth/login", web::post().to(auth::login))
+                .route("/auth/logout", web::post().to(auth::logout))
                 .route("/directory-tree", web::get().to(index::get_directory_tree))
                 .route("/funscripts", web::post().to(editor::save_funscript))
                 .route("/funscripts/calculate-draft-intensity", web::post().to(editor::calculate_draft_intensity))
+                .route("/funscripts/lint", web::get().to(lint::lint_funscripts))
                 .route("/calibration-mapping", web::get().to(calibration::get_bpm_mapping))
                 .route("/calibration-profiles", web::get().to(calibration::get_profiles))
                 .route("/calibration-profiles", web::post().to(calibration::save_profile))
                 .route("/calibration-activate", web::post().to(calibration::set_active_profile))
-                .route("/max-limit", web::post().to(calibration::set_max_limit))
+                .route("/bpm-table", web::get().to(calibration::get_bpm_table))
+                .route("/bpm-table", web::post().to(calibration::set_bpm_table))
+                .route("/bpm-table", web::delete().to(calibration::reset_bpm_table))
+                .route("/intensity-presets", web::get().to(intensity_presets::get_presets))
+                .route("/intensity-presets", web::post().to(intensity_presets::save_preset))
+                .route("/connection", web::get().to(devices::get_connection))
+                .route("/stop", web::get().to(devices::get_stop_state))
+                .route("/stop", web::post().to(devices::emergency_stop))
+                .route("/rearm", web::post().to(devices::rearm))
+                .route("/intiface-config", web::get().to(devices::get_intiface_config))
+                .route("/intiface-config", web::post().to(devices::update_intiface_config))
+                .route("/devices", web::get().to(devices::get_devices))
+                .route("/command-stats", web::get().to(devices::get_command_stats))
+                .route("/devices/{index}", web::post().to(devices::update_device))
+                .route("/max-limit", web::get().to(devices::get_max_limit))
+                .route("/max-limit", web::post().to(devices::set_max_limit))
+                .route("/virtual-device", web::get().to(devices::get_virtual_device))
+                .route("/remote", web::get().to(remote::get_remote_state))
+                .route("/live", web::get().to(live::handle_live))
+                .route("/sessions", web::get().to(sessions::get_sessions))
+                .route("/sessions/record/start", web::post().to(sessions::start_recording))
+                .route("/sessions/record/stop", web::post().to(sessions::stop_recording))
+                .route("/sessions/replay/stop", web::post().to(sessions::stop_replay))
+                .route("/sessions/{name}", web::get().to(sessions::get_session))
+                .route("/sessions/{name}/replay", web::post().to(sessions::start_replay))
+                .route("/remote", web::post().to(remote::post_remote_command))
                 .route("/recommendations/next", web::get().to(recommendations::get_next_recommendations))
                 .route("/recommendations/folder-start", web::get().to(recommendations::get_folder_start_recommendations))
         )
         .service(
             web::scope("/site")
                 .route("/", web::get().to(index::handle_index))
+                .route("/login", web::get().to(auth::handle_login_page))
                 .route("/analysis/durations", web::get().to(analysis::handle_check_durations))
                 .route("/editor", web::get().to(editor::handle_editor_page))
                 .route("/calibration", web::get().to(calibration::handle_calibration_page))
+                .route("/remote", web::get().to(remote::handle_remote_page))
                 .route("/video/{filename:.*}", web::get().to(video::handle_video))
                 .route("/funscripts/{filename:.*}", web::get().to(funscript::handle_funscript))
                 .route("/thumbnails/{filename:.*}", web::get().to(thumbnail::handle_thumbnail))
@@ -0,0 +1,175 @@
+// src/ws_protocol.rs
+
+//! Message schema of the /ws control socket
+//!
+//! Version 1 is the original protocol without a handshake: clients send the tagged
+//! control messages below (or the `{"stop": true}` shortcut) and the server answers with
+//! bare `{"error": ...}`, `{"watchdog_stop": ...}` and `{"device_event": ...}` objects.
+//!
+//! Version 2 starts with a `hello` from the client announcing its protocol version and
+//! capabilities. The server replies with a `welcome` holding the negotiated version, its
+//! own capabilities and the current state; from then on every server message is a tagged
+//! `ServerMessage`. Clients that never say hello stay on version 1, so old and new
+//! frontends can use the server at the same time.
+//!
+//! Only the session holding the control lease (see `control_lease`) may send driving
+//! commands; `control` messages tell every version 2 client who that is.
+
+use crate::{
+    buttplug::device_manager::{AppliedOutput, ConnectionState, DeviceEvent, ManagerEvent},
+    control_lease::{LeaseState, Role, SessionId},
+    handlers::devices::{SetMaxLimitPayload, UpdateDevicePayload},
+    remote::RemoteCommand,
+};
+use serde::{Deserialize, Serialize};
+
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Version assumed for clients that did not send a `hello`.
+pub const LEGACY_VERSION: u32 = 1;
+
+/// Client capability asking for periodic `intensity` frames.
+pub const CAP_INTENSITY: &str = "intensity";
+pub const SERVER_CAPABILITIES: &[&str] = &[
+    "playback",
+    "manual",
+    "emergency_stop",
+    "settings",
+    "device_events",
+    "control_lease",
+    "remote",
+    CAP_INTENSITY,
+];
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Handshake; switches the session to the negotiated version.
+    Hello {
+        version: u32,
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
+    Load {
+        path: String,
+        #[serde(default)]
+        variant: Option<String>,
+        #[serde(default)]
+        speed: Option<String>,
+        /// Intensity parameter preset; the defaults when missing.
+        #[serde(default)]
+        preset: Option<String>,
+    },
+    Play { at: f64 },
+    Pause { at: f64 },
+    Seek { at: f64 },
+    Sync { at: f64 },
+    Manual { o: Option<f64> },
+    Stop,
+    Rearm,
+    /// Same body as `POST /api/max-limit`.
+    SetMaxLimit(SetMaxLimitPayload),
+    /// Same body as `POST /api/devices/{index}`, plus the device index.
+    UpdateDevice {
+        index: u32,
+        #[serde(flatten)]
+        settings: Box<UpdateDevicePayload>,
+    },
+    /// Take the control lease from whoever holds it.
+    TakeControl,
+    ReleaseControl,
+}
+
+impl ClientMessage {
+    /// Whether the message drives the devices and therefore needs the control lease.
+    /// The emergency stop stays available to every session.
+    pub fn needs_control(&self) -> bool {
+        !matches!(
+            self,
+            ClientMessage::Hello { .. }
+                | ClientMessage::Stop
+                | ClientMessage::TakeControl
+                | ClientMessage::ReleaseControl
+        )
+    }
+}
+
+/// A session's view of the control lease.
+#[derive(Serialize, Debug, Clone)]
+pub struct ControlState {
+    pub session: SessionId,
+    pub role: Role,
+    #[serde(flatten)]
+    pub lease: LeaseState,
+}
+
+/// State sent with the `welcome` so a client starts in sync.
+#[derive(Serialize, Debug, Clone)]
+pub struct StateSnapshot {
+    pub stopped: bool,
+    /// Global max limit in percent.
+    pub max_limit: f64,
+    pub connection: ConnectionState,
+    /// Script position (ms) at which the watchdog stopped playback, if it did.
+    pub watchdog_stop: Option<f64>,
+    pub control: ControlState,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        capabilities: &'static [&'static str],
+        state: StateSnapshot,
+    },
+    Error { message: String },
+    WatchdogStop { at: f64 },
+    DeviceEvent { event: DeviceEvent },
+    StopState { stopped: bool },
+    /// Global max limit in percent.
+    Limit { limit: f64 },
+    /// Values the devices last received.
+    Intensity(AppliedOutput),
+    /// The control lease changed hands.
+    Control(ControlState),
+    /// Command from the remote page for the player in control.
+    Remote { command: RemoteCommand },
+}
+
+impl From<ManagerEvent> for ServerMessage {
+    fn from(event: ManagerEvent) -> Self {
+        match event {
+            ManagerEvent::Device(event) => ServerMessage::DeviceEvent { event },
+            ManagerEvent::StopState { stopped } => ServerMessage::StopState { stopped },
+            ManagerEvent::MaxLimit { limit } => ServerMessage::Limit { limit: limit * 100.0 },
+        }
+    }
+}
+
+impl ServerMessage {
+    /// Wire form for a client speaking `version`, or `None` when version 1 has no
+    /// equivalent message.
+    pub fn encode(&self, version: u32) -> Option<String> {
+        if version >= 2 {
+            return serde_json::to_string(self).ok();
+        }
+
+        let legacy = match self {
+            ServerMessage::Error { message } => serde_json::json!({ "error": message }),
+            ServerMessage::WatchdogStop { at } => serde_json::json!({ "watchdog_stop": { "at": at } }),
+            ServerMessage::DeviceEvent { event } => serde_json::json!({ "device_event": event }),
+            _ => return None,
+        };
+        Some(legacy.to_string())
+    }
+}
+
+/// Parse an incoming frame. `{"stop": true}` is accepted as a shortcut so any UI or
+/// remote can trigger the emergency stop without knowing the full protocol.
+pub fn parse_client_message(text: &str) -> Result<ClientMessage, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if value.get("stop").and_then(|v| v.as_bool()) == Some(true) {
+        return Ok(ClientMessage::Stop);
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
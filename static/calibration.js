@@ -1,6 +1,6 @@
 // static/calibration.js
 
-import { sendDeviceCommand } from './socket.js';
+import { sendManualIntensity } from './socket.js';
 import { loadFunscript } from './funscript_handler.js';
 import { clamp, toFunscriptPath } from './utils.js';
 
@@ -142,7 +142,7 @@ function selectPreset(preset, btn) {
     refreshDisplays();
 
     if (state.running) {
-        sendDeviceCommand(preset / 100.0, 0);
+        sendManualIntensity(preset / 100.0);
         state.lastSendTime = Date.now();
     }
 }
@@ -308,7 +308,7 @@ function startCalibration() {
     state.lastSendTime = Date.now();
 
     const intensity = state.selectedPreset / 100.0;
-    sendDeviceCommand(intensity, 0);
+    sendManualIntensity(intensity);
 
     updateInfoDisplays();
 
@@ -328,7 +328,7 @@ function sendLoop() {
     const stale = Date.now() - state.lastSendTime >= KEEPALIVE_MS;
 
     if (stale) {
-        sendDeviceCommand(intensity, 0);
+        sendManualIntensity(intensity);
 
         state.lastSendTime = Date.now();
         updateInfoDisplays();
@@ -346,7 +346,7 @@ function stopCalibration() {
     state.spinnerAnimId = null;
     state.lastTs = null;
 
-    sendDeviceCommand(0, 0);
+    sendManualIntensity(null);
     state.lastSentIntensity = null;
     state.lastSendTime = 0;
 
@@ -518,7 +518,7 @@ function renderMappingGraph() {
 
     const pad = 12;
     const iRange = 100;
-    const bRange = 270;
+    const bRange = bpmMapping.length > 0 ? bpmMapping[bpmMapping.length - 1][0] : 270;
 
     const xFor = (i) => pad + (i / iRange) * (cssWidth - pad * 2);
     const yFor = (b) => cssHeight - pad - (b / bRange) * (cssHeight - pad * 2);
@@ -532,7 +532,7 @@ function renderMappingGraph() {
         ctx.moveTo(x, pad);
         ctx.lineTo(x, cssHeight - pad);
 
-        const y = yFor(j * 60);
+        const y = yFor((bRange / 4) * j);
         ctx.moveTo(pad, y);
         ctx.lineTo(cssWidth - pad, y);
     }
@@ -647,7 +647,7 @@ export async function syncActiveProfileToRust(bpms) {
         await fetch('/api/calibration-activate', {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({ bpms })
+            body: JSON.stringify({ bpms, name: getProfileName() || null })
         });
         await reloadActiveFunscript();
     } catch (err) {
@@ -0,0 +1,66 @@
+// static/emergency_stop.js
+
+import { sendStop } from './socket.js';
+import { showTemporaryOverlayMessage } from './utils.js';
+
+let stopped = false;
+
+export function isEmergencyStopped() {
+    return stopped;
+}
+
+export async function initEmergencyStop() {
+    const button = document.getElementById('stop-button');
+    if (!button) return;
+
+    button.onclick = () => (stopped ? rearm() : triggerStop());
+
+    // Stop and re-arm from other clients or the remote
+    window.addEventListener('stop-state', (e) => {
+        if (e.detail.stopped && !stopped) {
+            window.dispatchEvent(new CustomEvent('devices-stopped'));
+        }
+        setStopped(e.detail.stopped);
+    });
+
+    try {
+        const resp = await fetch('/api/stop');
+        if (resp.ok) setStopped((await resp.json()).stopped);
+    } catch (err) {
+        console.error('Failed to read emergency stop state', err);
+    }
+}
+
+async function triggerStop() {
+    setStopped(true);
+    window.dispatchEvent(new CustomEvent('devices-stopped'));
+
+    try {
+        const resp = await fetch('/api/stop', { method: 'POST' });
+        if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+    } catch (err) {
+        // Fall back to the socket when the HTTP request does not go through
+        console.error('Emergency stop request failed', err);
+        sendStop();
+    }
+}
+
+async function rearm() {
+    try {
+        const resp = await fetch('/api/rearm', { method: 'POST' });
+        if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+        setStopped(false);
+        showTemporaryOverlayMessage('Devices re-armed. Press play to resume.');
+    } catch (err) {
+        console.error('Failed to re-arm devices', err);
+        showTemporaryOverlayMessage('Failed to re-arm devices.');
+    }
+}
+
+function setStopped(value) {
+    stopped = Boolean(value);
+    const button = document.getElementById('stop-button');
+    if (!button) return;
+    button.textContent = stopped ? 'Re-arm' : 'STOP';
+    button.classList.toggle('stopped', stopped);
+}
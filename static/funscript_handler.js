@@ -1,14 +1,14 @@
 // static/funscript_handler.js
 
+import { sendLoad } from './socket.js';
+
 export let funscriptActions = [];
 export let intensityActions = [];
 
 let currentVideoRawMaxIntensity = 0;
 let absoluteMax = 60;
-let vibrateMode = 'Rate';
 let selectedVariant = 'original';
 let selectedSpeed = 'normal';
-let lastBeatAt = null;
 let lastPeak = 0;
 let lastAvg = 0;
 let lastVolatility = 0;
@@ -42,6 +42,13 @@ export async function loadFunscript(funscriptUrl) {
         lastPeak = data?.peak || 0;
         lastAvg = data?.average || 0;
         lastVolatility = data?.volatility || 0;
+
+        const scriptPath = url.pathname.replace(/^\/site\/funscripts\//, '');
+        sendLoad(
+            decodeURIComponent(scriptPath),
+            selectedVariant,
+            selectedSpeed
+        );
     } catch (error) {
         console.error('Failed to load funscript:', error);
         funscriptActions = [];
@@ -75,14 +82,6 @@ export function getCurrentVideoMaxIntensity() {
     return Math.floor(currentVideoRawMaxIntensity);
 }
 
-export function setVibrateMode(mode) {
-    vibrateMode = mode;
-}
-
-export function getVibrateMode() {
-    return vibrateMode;
-}
-
 export function setAbsoluteMaximum(max) {
     absoluteMax = max;
 }
@@ -100,39 +99,6 @@ export function getFunscriptDuration() {
     return intensityActions[intensityActions.length - 1].at;
 }
 
-export function getCurrentBeatValue(currentTime) {
-    let lastBeatTime = null;
-    let nextBeatTime = null;
-
-    for (let i = 1; i < funscriptActions.length; i++) {
-        const prev = funscriptActions[i - 1];
-        const curr = funscriptActions[i];
-        if (prev.pos === 100 && curr.pos === 0) {
-            if (curr.at <= currentTime) lastBeatTime = curr.at;
-            else {
-                nextBeatTime = curr.at;
-                break;
-            }
-        }
-    }
-
-    if (lastBeatTime === null) return 0;
-
-    let vibrateValue = 0;
-    if (lastBeatAt !== lastBeatTime) {
-        vibrateValue = 1.0;
-        lastBeatAt = lastBeatTime;
-    } else {
-        const nextAt = nextBeatTime || currentTime + 500;
-        const t = Math.max(
-            0,
-            Math.min(1, (currentTime - lastBeatTime) / (nextAt - lastBeatTime))
-        );
-        vibrateValue = 1.0 - Math.sqrt(t);
-    }
-    return vibrateValue;
-}
-
 export function setSelectedFunscriptVariant(v) {
     selectedVariant = v && v.length ? v : 'original';
 }
@@ -1,6 +1,7 @@
 // static/main.js
 
 import { initDirectoryTree } from './directory_tree.js';
+import { initEmergencyStop } from './emergency_stop.js';
 import { initWebSocket } from './socket.js';
 import { createSettingsMenu, toggleSettingsMenu } from './settings_menu.js';
 import { setPlaybackData } from './video_player.js';
@@ -16,6 +17,8 @@ function initializeUI() {
     settingsBtn.style.display = 'block';
     settingsBtn.classList.add('player-button', 'btn');
 
+    initEmergencyStop();
+
     document.getElementById('toggle-directory').onclick = () => {
         document
             .getElementById('directory-container')
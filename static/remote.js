@@ -0,0 +1,107 @@
+// static/remote.js
+
+// Phone remote for the player: polls /api/remote and posts commands back to it
+
+const POLL_INTERVAL_MS = 2000;
+
+let state = null;
+
+async function sendCommand(command) {
+    const errorEl = document.getElementById('remote-error');
+    errorEl.textContent = '';
+    try {
+        const resp = await fetch('/api/remote', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(command)
+        });
+        if (!resp.ok) throw new Error(await resp.text());
+    } catch (err) {
+        errorEl.textContent = err.message || 'Command failed';
+    }
+    // Give the player a moment to report back before refreshing
+    setTimeout(refresh, 300);
+}
+
+function fillSelect(select, values, current) {
+    const key = values.join('|');
+    if (select.dataset.values !== key) {
+        select.innerHTML = '';
+        for (const v of values) {
+            const opt = document.createElement('option');
+            opt.value = v;
+            opt.text = v;
+            select.appendChild(opt);
+        }
+        select.dataset.values = key;
+    }
+    if (document.activeElement !== select && current) select.value = current;
+}
+
+function formatTime(ms) {
+    const total = Math.floor(ms / 1000);
+    const m = Math.floor(total / 60);
+    const s = String(total % 60).padStart(2, '0');
+    return `${m}:${s}`;
+}
+
+function render() {
+    const active = state.active;
+    const name = active ? active.path.split('/').pop() : null;
+    const position = state.position_ms != null ? ` (${formatTime(state.position_ms)})` : '';
+    document.getElementById('remote-now-playing').textContent = name
+        ? `${state.playing ? 'Playing' : 'Paused'}: ${name}${position}`
+        : 'Nothing loaded';
+    document.getElementById('remote-player').textContent = state.player
+        ? `Player: ${state.player}`
+        : 'No player connected';
+
+    const stopBtn = document.getElementById('remote-stop');
+    stopBtn.textContent = state.stopped ? 'Re-arm' : 'STOP';
+    stopBtn.classList.toggle('stopped', state.stopped);
+
+    const playBtn = document.getElementById('remote-play-pause');
+    playBtn.textContent = state.playing ? 'Pause' : 'Resume';
+    playBtn.disabled = !active || (state.stopped && !state.playing);
+
+    const limit = document.getElementById('remote-limit');
+    if (document.activeElement !== limit) {
+        limit.value = Math.round(state.max_limit);
+        document.getElementById('remote-limit-val').textContent = limit.value;
+    }
+
+    fillSelect(document.getElementById('remote-speed'), state.speeds, active?.speed);
+    fillSelect(document.getElementById('remote-variant'), state.variants, active?.variant);
+}
+
+async function refresh() {
+    try {
+        const resp = await fetch('/api/remote');
+        if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+        state = await resp.json();
+        render();
+    } catch (err) {
+        console.error('Failed to read remote state', err);
+    }
+}
+
+export function setup() {
+    document.getElementById('remote-stop').onclick = () =>
+        sendCommand({ action: state?.stopped ? 'rearm' : 'stop' });
+    document.getElementById('remote-play-pause').onclick = () =>
+        sendCommand({ action: state?.playing ? 'pause' : 'resume' });
+
+    const limit = document.getElementById('remote-limit');
+    limit.oninput = () => {
+        document.getElementById('remote-limit-val').textContent = limit.value;
+    };
+    limit.onchange = () => sendCommand({ action: 'set_max_limit', limit: Number(limit.value) });
+
+    const speed = document.getElementById('remote-speed');
+    speed.onchange = () => sendCommand({ action: 'set_speed', speed: speed.value });
+    const variant = document.getElementById('remote-variant');
+    variant.onchange = () => sendCommand({ action: 'set_variant', variant: variant.value });
+
+    refresh();
+    setInterval(refresh, POLL_INTERVAL_MS);
+}
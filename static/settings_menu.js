@@ -3,11 +3,9 @@
 import {
     setAbsoluteMaximum,
     getAbsoluteMaximum,
-    setVibrateMode,
     loadFunscript,
     setSelectedFunscriptVariant,
     getSelectedFunscriptVariant,
-    getVibrateMode,
     setSelectedSpeed,
     getSelectedSpeed,
     getLastIntensityStats
@@ -23,6 +21,34 @@ import { updateFunscriptDisplayBox } from './funscript_display_graphs.js';
 
 let initialized = false;
 
+// Roles available per actuator kind, and the update field each kind is sent in
+const DEVICE_ROLES = {
+    scalar: [
+        ['primary_stroke', 'Stroke'],
+        ['beat_vibration', 'Beat vibration'],
+        ['rate_vibration', 'Rate vibration'],
+        ['disabled', 'Disabled']
+    ],
+    linear: [
+        ['linear_position', 'Position'],
+        ['disabled', 'Disabled']
+    ],
+    rotate: [
+        ['rotate_alternating', 'Alternating'],
+        ['rotate_continuous', 'Continuous'],
+        ['disabled', 'Disabled']
+    ]
+};
+const ROLE_FIELDS = {
+    scalar: 'roles',
+    linear: 'linear_roles',
+    rotate: 'rotate_roles'
+};
+const CURVE_FIELDS = {
+    scalar: 'curves',
+    rotate: 'rotate_curves'
+};
+
 // ── Public API ─────────────────────────────────────────────────────────
 
 export function createSettingsMenu() {
@@ -42,6 +68,7 @@ export function toggleSettingsMenu() {
     }
 
     updateIntensityDisplay();
+    refreshDeviceList();
     document.body.style.overflow = 'hidden';
 
     const cleanup = () => {
@@ -157,6 +184,207 @@ async function syncMaxLimitToRust(limitValue) {
     }
 }
 
+async function updateDeviceOnServer(index, update) {
+    try {
+        const resp = await fetch(`/api/devices/${index}`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(update)
+        });
+        if (!resp.ok) throw new Error(await resp.text());
+    } catch (err) {
+        console.error('Failed to update device settings:', err);
+        showTemporaryOverlayMessage('Failed to update device settings.');
+    }
+}
+
+export async function refreshDeviceList() {
+    const list = document.getElementById('device-list');
+    if (!list) return;
+
+    let devices = [];
+    let connection = 'disconnected';
+    try {
+        const [devicesResp, connectionResp] = await Promise.all([
+            fetch('/api/devices'),
+            fetch('/api/connection')
+        ]);
+        if (!devicesResp.ok) throw new Error(`HTTP ${devicesResp.status}`);
+        devices = await devicesResp.json();
+        if (connectionResp.ok) connection = (await connectionResp.json()).state;
+    } catch (err) {
+        console.error('Failed to load devices', err);
+    }
+
+    list.innerHTML = '';
+    const status = document.createElement('div');
+    status.textContent = `Intiface: ${connection}`;
+    list.appendChild(status);
+
+    if (!devices.length) {
+        status.textContent += ' (no devices connected)';
+        return;
+    }
+
+    for (const device of devices) {
+        list.appendChild(buildDeviceEntry(device));
+    }
+}
+
+function buildDeviceEntry(device) {
+    const entry = document.createElement('div');
+    entry.className = 'device-entry';
+
+    const name = document.createElement('div');
+    name.className = 'device-name';
+    const status = [];
+    if (!device.connected) status.push('disconnected');
+    if (device.battery != null) status.push(`battery ${Math.round(device.battery * 100)}%`);
+    if (device.rssi != null) status.push(`${device.rssi} dBm`);
+    name.textContent = `${device.name} (#${device.index})`;
+    if (status.length) name.textContent += ` · ${status.join(' · ')}`;
+    entry.appendChild(name);
+
+    if (device.virtual) {
+        entry.appendChild(buildVirtualReadout());
+    }
+
+    for (const actuator of device.actuators) {
+        const row = document.createElement('div');
+        row.className = 'input-row';
+
+        const label = document.createElement('span');
+        label.textContent = `${actuator.actuator_type} ${actuator.index}`;
+        if (actuator.last_value != null) {
+            label.textContent += ` (${Math.round(actuator.last_value * 100)}%)`;
+        }
+        label.title = actuator.description;
+
+        const select = document.createElement('select');
+        select.className = 'settings-select';
+        for (const [value, text] of DEVICE_ROLES[actuator.kind]) {
+            const opt = document.createElement('option');
+            opt.value = value;
+            opt.text = text;
+            select.appendChild(opt);
+        }
+        select.value = actuator.role;
+        select.addEventListener('change', () =>
+            updateDeviceOnServer(device.index, {
+                [ROLE_FIELDS[actuator.kind]]: {
+                    [actuator.index]: select.value
+                }
+            })
+        );
+
+        row.append(label, select);
+        entry.appendChild(row);
+
+        if (actuator.curve) {
+            entry.appendChild(buildCurveRows(device.index, actuator));
+        }
+    }
+
+    const numberFields = [
+        ['Scale %', 'scale', device.scale * 100, 100],
+        ['Soft start ms', 'soft_start_ms', device.soft_start_ms, 10000]
+    ];
+    for (const [text, field, value, max] of numberFields) {
+        entry.appendChild(
+            buildDeviceNumberRow(text, Math.round(value), max, (v) =>
+                updateDeviceOnServer(device.index, { [field]: v })
+            )
+        );
+    }
+
+    return entry;
+}
+
+// Live actuator values of the virtual device, polled while the entry is shown
+function buildVirtualReadout() {
+    const readout = document.createElement('div');
+    readout.className = 'virtual-readout';
+
+    let since = 0;
+    let started = false;
+    const poll = async () => {
+        // Stop once the entry was removed (menu refreshed or device gone)
+        if (started && !readout.isConnected) return;
+        started = true;
+        try {
+            // Only ask for commands not seen yet; the readout shows just the state
+            const resp = await fetch(`/api/virtual-device?since=${since}`);
+            if (resp.ok) {
+                const { state, commands } = await resp.json();
+                if (commands.length) since = commands[commands.length - 1].seq + 1;
+                const parts = [
+                    ...Object.entries(state.scalar).map(([i, v]) => `S${i} ${Math.round(v * 100)}%`),
+                    ...Object.entries(state.linear).map(([i, v]) => `L${i} ${Math.round(v * 100)}%`),
+                    ...Object.entries(state.rotate).map(
+                        ([i, [v, cw]]) => `R${i} ${Math.round(v * 100)}% ${cw ? '↻' : '↺'}`
+                    )
+                ];
+                readout.textContent = parts.length ? parts.join('  ') : 'idle';
+            }
+        } catch (err) {
+            console.error('Failed to poll virtual device', err);
+        }
+        setTimeout(poll, 250);
+    };
+    poll();
+
+    return readout;
+}
+
+// Limit, floor, gamma and rise/fall limits of one scalar/rotate actuator
+function buildCurveRows(deviceIndex, actuator) {
+    const rows = document.createDocumentFragment();
+    const commit = (field) => (v) =>
+        updateDeviceOnServer(deviceIndex, {
+            [CURVE_FIELDS[actuator.kind]]: {
+                [actuator.index]: { [field]: v }
+            }
+        });
+
+    const curve = actuator.curve;
+    rows.append(
+        buildDeviceNumberRow('Limit %', Math.round(curve.limit * 100), 100, commit('limit')),
+        buildDeviceNumberRow('Floor %', Math.round(curve.floor * 100), 100, commit('floor')),
+        buildDeviceNumberRow('Gamma', curve.gamma, 10, commit('gamma'), 0.1),
+        buildDeviceNumberRow('Accel %/s', Math.round(curve.max_rise_per_sec * 100), 1000, commit('max_rise')),
+        buildDeviceNumberRow('Decel %/s', Math.round(curve.max_fall_per_sec * 100), 1000, commit('max_fall'))
+    );
+    return rows;
+}
+
+function buildDeviceNumberRow(text, value, max, onCommit, step = 1) {
+    const row = document.createElement('div');
+    row.className = 'input-row';
+    const label = document.createElement('span');
+    label.textContent = text;
+    const input = document.createElement('input');
+    input.type = 'number';
+    input.min = '0';
+    input.max = max.toString();
+    input.step = step.toString();
+    input.className = 'settings-input';
+    input.value = value.toString();
+    input.addEventListener('change', () => {
+        const parsed = step < 1 ? parseFloat(input.value) : parseInt(input.value, 10);
+        if (parsed >= 0 && parsed <= max) {
+            value = parsed;
+            onCommit(parsed);
+        } else {
+            showTemporaryOverlayMessage(
+                `Please enter a value between 0 and ${max}.`
+            );
+            input.value = value.toString();
+        }
+    });
+    row.append(label, input);
+    return row;
+}
+
 // ── Helpers ────────────────────────────────────────────────────────────
 
 function getCurrentVideoPath() {
@@ -188,9 +416,11 @@ function ensureInit() {
     initVariantSelect(menu);
     initCalibrationButton(menu);
     initHardLimit(menu);
-    initVibrateMode(menu);
+    initDeviceList(menu);
     initSpeedMode(menu);
     initEditorButton(menu);
+    initLogoutButton(menu);
+    initRemoteCommands(menu);
 }
 
 function initSBSToggle(menu) {
@@ -263,6 +493,7 @@ function initHardLimit(menu) {
 
     input.value = getAbsoluteMaximum().toString();
     input.disabled = true;
+    syncMaxLimitToRust(getAbsoluteMaximum());
 
     if (lockBtn) {
         lockBtn.addEventListener('click', () => {
@@ -281,6 +512,14 @@ function initHardLimit(menu) {
         });
     }
 
+    // Limit changed by another client
+    window.addEventListener('limit-changed', (e) => {
+        const limit = Math.round(e.detail.limit);
+        if (!input.disabled || limit === getAbsoluteMaximum()) return;
+        setAbsoluteMaximum(limit);
+        input.value = limit.toString();
+    });
+
     input.addEventListener('change', () => {
         const value = parseInt(input.value, 10);
         if (value >= 0 && value <= 100) {
@@ -295,12 +534,18 @@ function initHardLimit(menu) {
     });
 }
 
-function initVibrateMode(menu) {
-    const select = menu.querySelector('#vibrate-mode-select');
-    if (!select) return;
+function initDeviceList(menu) {
+    const refreshBtn = menu.querySelector('#refresh-devices-button');
+    if (refreshBtn) {
+        refreshBtn.addEventListener('click', refreshDeviceList);
+    }
 
-    select.value = getVibrateMode?.() ?? 'Rate';
-    select.addEventListener('change', () => setVibrateMode(select.value));
+    // Keep the list current while the menu is open, unless a field is being edited
+    window.addEventListener('device-event', () => {
+        const list = document.getElementById('device-list');
+        if (!menu.classList.contains('visible') || list?.contains(document.activeElement)) return;
+        refreshDeviceList();
+    });
 }
 
 function initEditorButton(menu) {
@@ -322,6 +567,55 @@ function initEditorButton(menu) {
     });
 }
 
+// Speed and variant changes from the remote page go through the same selects as the menu
+function initRemoteCommands(menu) {
+    window.addEventListener('remote-command', (e) => {
+        const { action, speed, variant } = e.detail;
+        let select = null;
+        let value = null;
+        if (action === 'set_speed') {
+            select = menu.querySelector('#speed-mode-select');
+            value = speed;
+        } else if (action === 'set_variant') {
+            select = menu.querySelector('#funscript-variant-select');
+            value = variant;
+        }
+        if (!select || select.value === value) return;
+
+        if (![...select.options].some((o) => o.value === value)) {
+            const opt = document.createElement('option');
+            opt.value = value;
+            opt.text = value;
+            select.appendChild(opt);
+        }
+        select.value = value;
+        select.dispatchEvent(new Event('change'));
+    });
+}
+
+// Only shown when the server requires a login
+async function initLogoutButton(menu) {
+    const btn = menu.querySelector('#logout-button');
+    if (!btn) return;
+
+    try {
+        const resp = await fetch('/api/auth/status');
+        if (!resp.ok) return;
+        const status = await resp.json();
+        if (!status.enabled) return;
+        btn.textContent = `Log out (${status.user})`;
+        btn.classList.remove('hidden');
+    } catch (err) {
+        console.error('Failed to read login state', err);
+        return;
+    }
+
+    btn.addEventListener('click', async () => {
+        await fetch('/api/auth/logout', { method: 'POST' });
+        window.location.href = '/site/login';
+    });
+}
+
 function initSpeedMode(menu) {
     const select = menu.querySelector('#speed-mode-select');
     if (!select) return;
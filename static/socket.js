@@ -1,13 +1,14 @@
 // static/socket.js
 
-import { getAbsoluteMaximum, getVibrateMode } from './funscript_handler.js';
+import { showTemporaryOverlayMessage } from './utils.js';
 
 const WS_PORT = 5441;
 const RECONNECT_DELAY_MS = 1000;
-const VIBRATE_DEADZONE = 0.03;
-const VIBRATE_SCALE = 1.5;
+const PROTOCOL_VERSION = 2;
 
 let ws = null;
+let lastLoadMessage = null;
+let controlRole = null;
 
 export function initWebSocket() {
     if (
@@ -21,35 +22,149 @@ export function initWebSocket() {
 
     try {
         ws = new WebSocket(`ws://${window.location.hostname}:${WS_PORT}/ws`);
-        ws.onopen = () => console.log('WebSocket connected');
+        ws.onopen = () => {
+            console.log('WebSocket connected');
+            send({
+                type: 'hello',
+                version: PROTOCOL_VERSION,
+                capabilities: ['device_events']
+            });
+            // Restore the server-side timeline after a reconnect
+            if (lastLoadMessage) send(lastLoadMessage);
+        };
         ws.onerror = (error) => console.error('WebSocket error:', error);
         ws.onclose = (event) => {
             console.log(`WebSocket closed: ${event.code} ${event.reason}`);
             setTimeout(initWebSocket, RECONNECT_DELAY_MS);
         };
-        ws.onmessage = (event) => console.log('WebSocket message:', event.data);
+        ws.onmessage = handleMessage;
     } catch (e) {
         console.error('WebSocket initialization error:', e);
     }
 }
 
-export function sendDeviceCommand(oscillate, vibrate) {
-    if (!ws || ws.readyState !== WebSocket.OPEN) return;
+function handleMessage(event) {
+    console.log('WebSocket message:', event.data);
 
-    const maxOscillate = getAbsoluteMaximum() / 100;
-    let vibrateValue = vibrate;
+    let data;
+    try {
+        data = JSON.parse(event.data);
+    } catch {
+        return;
+    }
 
-    if (getVibrateMode() === 'Rate') {
-        vibrateValue =
-            vibrateValue < VIBRATE_DEADZONE
-                ? 0.0
-                : (vibrateValue - VIBRATE_DEADZONE) * VIBRATE_SCALE;
+    switch (data.type) {
+        case 'welcome':
+            window.dispatchEvent(
+                new CustomEvent('stop-state', { detail: { stopped: data.state.stopped } })
+            );
+            if (data.state.watchdog_stop != null) {
+                handleWatchdogStop({ at: data.state.watchdog_stop });
+            }
+            handleControl(data.state.control);
+            break;
+        case 'control':
+            handleControl(data);
+            break;
+        case 'remote':
+            window.dispatchEvent(
+                new CustomEvent('remote-command', { detail: data.command })
+            );
+            break;
+        case 'watchdog_stop':
+            handleWatchdogStop({ at: data.at });
+            break;
+        case 'device_event':
+            handleDeviceEvent(data.event);
+            break;
+        case 'stop_state':
+            window.dispatchEvent(
+                new CustomEvent('stop-state', { detail: { stopped: data.stopped } })
+            );
+            break;
+        case 'limit':
+            window.dispatchEvent(
+                new CustomEvent('limit-changed', { detail: { limit: data.limit } })
+            );
+            break;
+        case 'error':
+            console.warn('Server rejected command:', data.message);
+            break;
     }
+}
 
-    ws.send(
-        JSON.stringify({
-            o: Math.max(0, Math.min(oscillate, maxOscillate)),
-            v: Math.max(0, Math.min(vibrateValue, 1.0))
-        })
+// The server halted the devices while this client was unreachable
+function handleWatchdogStop(detail) {
+    showTemporaryOverlayMessage(
+        'Devices were stopped because the connection was lost.',
+        4000
     );
+    window.dispatchEvent(new CustomEvent('devices-stopped', { detail }));
+}
+
+// Only the session holding the control lease drives the devices; a free lease is
+// claimed by the first command, so the button only shows while someone else holds it.
+function handleControl(control) {
+    const observing = control.role === 'observer' && control.controller != null;
+    if (controlRole === 'controller' && control.role === 'observer') {
+        showTemporaryOverlayMessage(
+            `Another client (${control.controller_addr ?? 'unknown'}) took control.`,
+            4000
+        );
+    }
+    controlRole = control.role;
+
+    const button = document.getElementById('take-control-button');
+    if (button) {
+        button.classList.toggle('visible', observing);
+        button.onclick = takeControl;
+    }
+    window.dispatchEvent(new CustomEvent('control-state', { detail: control }));
+}
+
+function handleDeviceEvent(event) {
+    if (event.type === 'low_battery') {
+        const percent = Math.round(event.battery * 100);
+        showTemporaryOverlayMessage(
+            `${event.name} battery low (${percent}%)` +
+                (event.paused ? ', playback stopped.' : '.'),
+            4000
+        );
+        if (event.paused) {
+            window.dispatchEvent(new CustomEvent('devices-stopped'));
+        }
+    }
+    window.dispatchEvent(new CustomEvent('device-event', { detail: event }));
+}
+
+function send(message) {
+    if (!ws || ws.readyState !== WebSocket.OPEN) return;
+    ws.send(JSON.stringify(message));
+}
+
+// Take the control lease from the client currently driving the devices
+export function takeControl() {
+    send({ type: 'take_control' });
+    if (lastLoadMessage) send(lastLoadMessage);
+}
+
+// Load a funscript into the server-side playback engine
+export function sendLoad(path, variant, speed) {
+    lastLoadMessage = { type: 'load', path, variant, speed };
+    send(lastLoadMessage);
+}
+
+// Report a transport event ('play', 'pause', 'seek' or 'sync') at a video time in ms
+export function sendPlayback(type, atMs) {
+    send({ type, at: atMs });
+}
+
+// Latched emergency stop; released through /api/rearm
+export function sendStop() {
+    send({ stop: true });
+}
+
+// Drive the oscillator directly (calibration). Pass null to release the override.
+export function sendManualIntensity(oscillate) {
+    send({ type: 'manual', o: oscillate });
 }
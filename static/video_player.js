@@ -2,10 +2,6 @@
 
 import {
     loadFunscript,
-    getCurrentIntensity,
-    getCurrentVideoMaxIntensity,
-    getVibrateMode,
-    getCurrentBeatValue,
     setSelectedFunscriptVariant,
     getFunscriptDuration
 } from './funscript_handler.js';
@@ -13,7 +9,8 @@ import {
     createFunscriptDisplayBox,
     updateFunscriptDisplayBox
 } from './funscript_display_graphs.js';
-import { sendDeviceCommand } from './socket.js';
+import { sendPlayback } from './socket.js';
+import { isEmergencyStopped } from './emergency_stop.js';
 import {
     refreshVariantsForCurrentVideo,
     updateIntensityDisplay,
@@ -21,7 +18,6 @@ import {
     isHardLimitUnlocked
 } from './settings_menu.js';
 import {
-    lerp,
     toFunscriptPath,
     getFunscriptStats,
     showTemporaryOverlayMessage,
@@ -38,12 +34,12 @@ const DISABLE_FULLSCREEN = ['1', 'true', 'yes'].includes(
     (urlParams.get('no_fullscreen') || '').toLowerCase()
 );
 const TRANSITION_DURATION = 1000;
+const SYNC_INTERVAL_MS = 1000;
 
 const state = {
     currentAnimationFrame: null,
     cancelAnimationTimeout: null,
-    transitionStartTime: Date.now(),
-    transitionTargetValue: 1,
+    syncTimer: null,
     globalTree: null,
     globalFunscriptMap: null,
     currentVideoRelativePath: null,
@@ -54,41 +50,28 @@ const state = {
     videoEndedCancelled: false
 };
 
-function computeOscillateValue(intensity, progress) {
-    if (intensity === undefined) return 0;
-    return lerp(0, intensity / 100, progress);
-}
+// Keep the video in step with the server when it stopped the devices on its own
+window.addEventListener('devices-stopped', () => {
+    const videoElement = document.querySelector('#video-player video');
+    if (videoElement && !videoElement.paused) videoElement.pause();
+});
 
-function computeVibrateValue(currentTime, intensity, progress) {
-    if (getVibrateMode() === 'Rate') {
-        if (intensity === undefined) return 0;
-        return (
-            lerp(
-                0,
-                (intensity / 100) * getCurrentVideoMaxIntensity(),
-                progress
-            ) / 100
-        );
+// Pause and resume from the remote page
+window.addEventListener('remote-command', (e) => {
+    const videoElement = document.querySelector('#video-player video');
+    if (!videoElement) return;
+    if (e.detail.action === 'pause' && !videoElement.paused) {
+        videoElement.pause();
+    } else if (e.detail.action === 'resume' && videoElement.paused) {
+        if (isEmergencyStopped()) return;
+        videoElement.play().catch((err) => console.error('Remote resume failed', err));
     }
-    const beatValue = getCurrentBeatValue(currentTime);
-    return beatValue !== undefined ? lerp(0, beatValue, progress) : 0;
-}
+});
 
 function updateProgressBars(videoElement) {
     const currentTime = videoElement.currentTime * 1000;
-    const intensity = getCurrentIntensity(currentTime);
     updateFunscriptDisplayBox(currentTime);
 
-    const elapsed = Date.now() - state.transitionStartTime;
-    const progress = Math.abs(
-        state.transitionTargetValue - Math.min(elapsed / TRANSITION_DURATION, 1)
-    );
-
-    sendDeviceCommand(
-        computeOscillateValue(intensity, progress),
-        computeVibrateValue(currentTime, intensity, progress)
-    );
-
     const funscriptEnd = getFunscriptDuration();
 
     if (funscriptEnd > 0 && currentTime < funscriptEnd - 500) {
@@ -127,6 +110,25 @@ function exitFullscreen() {
         document.exitFullscreen()?.catch(() => {});
 }
 
+// The server extrapolates the position itself; the sync only corrects drift and keeps
+// the watchdog fed. A timer instead of the animation frame loop, which stops in
+// background tabs.
+function startSyncTimer(videoElement) {
+    stopSyncTimer();
+    state.syncTimer = setInterval(() => {
+        if (!videoElement.paused) {
+            sendPlayback('sync', videoElement.currentTime * 1000);
+        }
+    }, SYNC_INTERVAL_MS);
+}
+
+function stopSyncTimer() {
+    if (state.syncTimer) {
+        clearInterval(state.syncTimer);
+        state.syncTimer = null;
+    }
+}
+
 function cancelCurrentAnimation() {
     if (state.currentAnimationFrame) {
         cancelAnimationFrame(state.currentAnimationFrame);
@@ -151,7 +153,8 @@ export async function playVideo(
     if (errorOverlay) errorOverlay.classList.add('hidden');
 
     cancelCurrentAnimation();
-    sendDeviceCommand(0, 0);
+    stopSyncTimer();
+    sendPlayback('pause', 0);
     updateSbsPlayingState();
 
     const videoPlayer = document.getElementById('video-player');
@@ -179,6 +182,10 @@ export async function playVideo(
         state.videoEndedCancelled = false;
     };
 
+    videoElement.onseeked = () => {
+        sendPlayback('seek', videoElement.currentTime * 1000);
+    };
+
     videoElement.onplay = () => {
         if (isHardLimitUnlocked()) {
             videoElement.pause();
@@ -187,14 +194,21 @@ export async function playVideo(
             );
             return;
         }
+        if (isEmergencyStopped()) {
+            videoElement.pause();
+            showTemporaryOverlayMessage(
+                'Playback refused: emergency stop is active. Press Re-arm first.'
+            );
+            return;
+        }
 
         if (state.cancelAnimationTimeout) {
             clearTimeout(state.cancelAnimationTimeout);
             state.cancelAnimationTimeout = null;
         }
         cancelCurrentAnimation();
-        state.transitionStartTime = Date.now();
-        state.transitionTargetValue = 0;
+        sendPlayback('play', videoElement.currentTime * 1000);
+        startSyncTimer(videoElement);
         state.currentAnimationFrame = requestAnimationFrame(() =>
             updateProgressBars(videoElement)
         );
@@ -209,8 +223,8 @@ export async function playVideo(
             cancelCurrentAnimation,
             TRANSITION_DURATION + 100
         );
-        state.transitionStartTime = Date.now();
-        state.transitionTargetValue = 1;
+        stopSyncTimer();
+        sendPlayback('pause', videoElement.currentTime * 1000);
 
         lockPortrait().finally(() => {
             exitFullscreen();